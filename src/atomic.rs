@@ -0,0 +1,197 @@
+use std;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use file::NamedTempFile;
+use util;
+use Builder;
+
+/// Atomically replace the contents of a file.
+///
+/// This is a convenience wrapper around [`AtomicWriteFile`]: readers of `path` will either
+/// see the old contents or all of `contents`, never a partially written file, even if the
+/// system crashes halfway through.
+///
+/// # Errors
+///
+/// If the contents cannot be written or the file cannot be moved into place, `Err` is returned
+/// and `path` is left untouched.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate tempfile;
+/// # use std::io;
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// tempfile::write_atomic("./config.toml", "answer = 42\n")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`AtomicWriteFile`]: struct.AtomicWriteFile.html
+pub fn write_atomic<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+    let mut file = AtomicWriteFile::open(path)?;
+    file.write_all(contents.as_ref())?;
+    file.commit()
+}
+
+/// A file that atomically replaces a target path once it's committed.
+///
+/// `AtomicWriteFile` writes into a [`NamedTempFile`] created next to the target, so that it's
+/// on the same filesystem, and only moves it over the target on [`AtomicWriteFile::commit()`].
+/// Before renaming, the data is flushed to disk. After renaming, so is the directory entry.
+/// Readers of the target path therefore either see the old file or the complete new one.
+///
+/// If the `AtomicWriteFile` is dropped without being committed, the temporary file is deleted
+/// and the target is left untouched.
+///
+/// If the target already exists, the new file is created with the same permissions.
+///
+/// # Resource Leaking
+///
+/// See [the resource leaking][resource-leaking] docs on `NamedTempFile`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate tempfile;
+/// # use std::io::{self, Write};
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// use tempfile::AtomicWriteFile;
+///
+/// let mut file = AtomicWriteFile::open("./index.json")?;
+/// writeln!(file, "{{")?;
+/// writeln!(file, "  \"entries\": []")?;
+/// writeln!(file, "}}")?;
+///
+/// // Nothing has happened to `./index.json` until now.
+/// file.commit()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`NamedTempFile`]: struct.NamedTempFile.html
+/// [`AtomicWriteFile::commit()`]: #method.commit
+/// [resource-leaking]: struct.NamedTempFile.html#resource-leaking
+pub struct AtomicWriteFile {
+    file: NamedTempFile,
+    target: PathBuf,
+}
+
+impl AtomicWriteFile {
+    /// Start writing a file that will replace `target` once it's committed.
+    ///
+    /// # Errors
+    ///
+    /// If the temporary file can not be created in the target's directory, `Err` is returned.
+    pub fn open<P: AsRef<Path>>(target: P) -> io::Result<AtomicWriteFile> {
+        let target = target.as_ref().to_owned();
+
+        let mut builder = Builder::new();
+        match fs::metadata(&target) {
+            Ok(metadata) => {
+                builder.permissions(metadata.permissions());
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        let file = builder.tempfile_in(util::parent_dir(&target))?;
+        Ok(AtomicWriteFile { file, target })
+    }
+
+    /// Get the path that will be replaced when the file is committed.
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    /// Get a reference to the underlying file.
+    pub fn as_file(&self) -> &File {
+        self.file.as_file()
+    }
+
+    /// Get a mutable reference to the underlying file.
+    pub fn as_file_mut(&mut self) -> &mut File {
+        self.file.as_file_mut()
+    }
+
+    /// Flush the file to disk and atomically move it over the target path.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be synced or moved to the target path, `Err` is returned, the
+    /// temporary file is deleted and the target is left untouched. If syncing the target's
+    /// directory fails, `Err` is returned even though the target has already been replaced.
+    pub fn commit(mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.file.persist_durable(&self.target)?;
+        Ok(())
+    }
+
+    /// Delete the temporary file without touching the target.
+    ///
+    /// Use this if you want to detect errors in deleting the file.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be deleted, `Err` is returned.
+    pub fn discard(self) -> io::Result<()> {
+        self.file.close()
+    }
+}
+
+impl fmt::Debug for AtomicWriteFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AtomicWriteFile({:?})", self.target)
+    }
+}
+
+impl Read for AtomicWriteFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Write for AtomicWriteFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for AtomicWriteFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+#[cfg(unix)]
+impl std::os::unix::io::AsRawFd for AtomicWriteFile {
+    #[inline]
+    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        self.as_file().as_raw_fd()
+    }
+}
+
+#[cfg(windows)]
+impl std::os::windows::io::AsRawHandle for AtomicWriteFile {
+    #[inline]
+    fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+        self.as_file().as_raw_handle()
+    }
+}
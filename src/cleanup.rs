@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use dir;
+use util;
+
+// Whether `cleanup_on_exit` has been called. Until then, nothing is registered.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// The temporary files and directories that are still alive, by the path they were registered
+// with, with their absolute path and whether they are directories. Allocated on first use and
+// never freed.
+static REGISTRY: AtomicPtr<Mutex<Registry>> = AtomicPtr::new(ptr::null_mut());
+
+type Registry = HashMap<PathBuf, (PathBuf, bool)>;
+
+fn registry() -> MutexGuard<'static, Registry> {
+    let mut registry = REGISTRY.load(Ordering::SeqCst);
+    if registry.is_null() {
+        let new = Box::into_raw(Box::new(Mutex::new(HashMap::new())));
+        registry = match REGISTRY.compare_exchange(
+            ptr::null_mut(),
+            new,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        ) {
+            Ok(_) => new,
+            Err(existing) => {
+                drop(unsafe { Box::from_raw(new) });
+                existing
+            }
+        };
+    }
+    // Removing the paths on exit must work even if a thread panicked while holding the lock.
+    match unsafe { (*registry).lock() } {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+// pub(crate)
+pub fn register_file(path: &Path) {
+    register(path, false);
+}
+
+// pub(crate)
+pub fn register_dir(path: &Path) {
+    register(path, true);
+}
+
+// Relative paths are resolved now: by the time they're removed, the working directory may
+// have changed. If it can't be determined, the path isn't registered rather than risking
+// removing something else.
+fn register(path: &Path, is_dir: bool) {
+    if !ENABLED.load(Ordering::SeqCst) {
+        return;
+    }
+    let absolute = if path.is_absolute() {
+        path.to_owned()
+    } else {
+        match env::current_dir() {
+            Ok(dir) => dir.join(path),
+            Err(_) => return,
+        }
+    };
+    registry().insert(path.to_owned(), (absolute, is_dir));
+}
+
+// pub(crate)
+pub fn unregister(path: &Path) {
+    if ENABLED.load(Ordering::SeqCst) {
+        registry().remove(path);
+    }
+}
+
+// Delete everything that's still registered.
+#[cfg(unix)]
+fn remove_all() {
+    let paths: Vec<_> = registry().drain().map(|(_, entry)| entry).collect();
+    for (path, is_dir) in paths {
+        if util::should_keep(false, &path) {
+            continue;
+        }
+        let _ = if is_dir {
+            dir::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+    }
+}
+
+/// Delete the remaining temporary files and directories when the process exits or is
+/// interrupted.
+///
+/// Normally, [`NamedTempFile`], [`TempPath`] and [`TempDir`] are only deleted by their
+/// destructors, which don't run when the process calls [`std::process::exit()`] or is killed
+/// by a signal. After calling this function, the paths of temporary files and directories
+/// created from then on are recorded until they are deleted, persisted or kept, and whatever
+/// is left is deleted:
+///
+/// * when the process exits through `exit`, from an `atexit` hook,
+/// * when the process receives `SIGINT`, `SIGTERM`, `SIGHUP` or `SIGQUIT`, after which the
+///   signal's default action terminates the process as usual.
+///
+/// Signals that already have a handler or are ignored when this function is called are left
+/// alone, so it doesn't interfere with applications that handle them themselves. The signal
+/// handler only wakes a background thread that does the actual deletion.
+///
+/// Only the process that called this function cleans up: child processes created with `fork`
+/// inherit the registered paths, but neither delete them on exit nor on a signal, since the
+/// parent may still be using them, e.g. when daemonizing.
+///
+/// Calling this function more than once has no further effect.
+///
+/// # Errors
+///
+/// If the background thread can't be started, `Err` is returned and nothing is installed.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// # use std::io;
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// tempfile::cleanup_on_exit()?;
+///
+/// let dir = tempfile::tempdir()?;
+/// // Even if the process is interrupted or exits from here on, `dir` is deleted.
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`NamedTempFile`]: struct.NamedTempFile.html
+/// [`TempPath`]: struct.TempPath.html
+/// [`TempDir`]: struct.TempDir.html
+/// [`std::process::exit()`]: https://doc.rust-lang.org/std/process/fn.exit.html
+#[cfg(unix)]
+pub fn cleanup_on_exit() -> ::std::io::Result<()> {
+    unix::install()
+}
+
+#[cfg(unix)]
+mod unix {
+    use libc::{self, c_int, c_void, sigaction, sighandler_t};
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::mem;
+    use std::os::unix::io::FromRawFd;
+    use std::ptr;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::thread;
+
+    const SIGNALS: &[c_int] = &[libc::SIGINT, libc::SIGTERM, libc::SIGHUP, libc::SIGQUIT];
+
+    static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+    // The write end of the pipe the signal handler wakes the cleanup thread with.
+    static PIPE: AtomicUsize = AtomicUsize::new(0);
+
+    // The process that installed the hooks. Forked children inherit them, but must neither
+    // delete the parent's files nor wake the parent's cleanup thread.
+    static PID: AtomicUsize = AtomicUsize::new(0);
+
+    fn is_installer() -> bool {
+        PID.load(Ordering::SeqCst) == unsafe { libc::getpid() } as usize
+    }
+
+    pub fn install() -> io::Result<()> {
+        if INSTALLED.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        if let Err(e) = start_thread() {
+            INSTALLED.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+        PID.store(unsafe { libc::getpid() } as usize, Ordering::SeqCst);
+        super::ENABLED.store(true, Ordering::SeqCst);
+        unsafe {
+            for &signal in SIGNALS {
+                let mut old: sigaction = mem::zeroed();
+                if libc::sigaction(signal, ptr::null(), &mut old) != 0
+                    || old.sa_sigaction != libc::SIG_DFL
+                {
+                    continue;
+                }
+                let mut new: sigaction = mem::zeroed();
+                new.sa_sigaction = on_signal as extern "C" fn(c_int) as sighandler_t;
+                new.sa_flags = libc::SA_RESTART;
+                libc::sigemptyset(&mut new.sa_mask);
+                libc::sigaction(signal, &new, ptr::null_mut());
+            }
+            libc::atexit(on_exit);
+        }
+        Ok(())
+    }
+
+    fn start_thread() -> io::Result<()> {
+        let mut fds = [0; 2];
+        unsafe {
+            if libc::pipe(fds.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            libc::fcntl(fds[0], libc::F_SETFD, libc::FD_CLOEXEC);
+            libc::fcntl(fds[1], libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+        let mut receiver = unsafe { File::from_raw_fd(fds[0]) };
+        let spawned = thread::Builder::new()
+            .name("tempfile-cleanup".to_owned())
+            .spawn(move || {
+                let mut signal = [0u8];
+                if receiver.read_exact(&mut signal).is_ok() {
+                    super::remove_all();
+                    // Let the signal do what it would have done without us.
+                    unsafe {
+                        libc::signal(c_int::from(signal[0]), libc::SIG_DFL);
+                        libc::raise(c_int::from(signal[0]));
+                    }
+                }
+            });
+        match spawned {
+            Ok(_) => {
+                PIPE.store(fds[1] as usize, Ordering::SeqCst);
+                Ok(())
+            }
+            Err(e) => {
+                unsafe {
+                    libc::close(fds[1]);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    // Only async-signal-safe functions may be called here, so leave the work to the thread.
+    extern "C" fn on_signal(signal: c_int) {
+        if !is_installer() {
+            unsafe {
+                libc::signal(signal, libc::SIG_DFL);
+                libc::raise(signal);
+            }
+            return;
+        }
+        let signal = signal as u8;
+        unsafe {
+            libc::write(
+                PIPE.load(Ordering::SeqCst) as c_int,
+                &signal as *const u8 as *const c_void,
+                1,
+            );
+        }
+    }
+
+    extern "C" fn on_exit() {
+        if is_installer() {
+            super::remove_all();
+        }
+    }
+}
@@ -0,0 +1,11 @@
+#[cfg(unix)]
+mod unix;
+
+#[cfg(unix)]
+pub use self::unix::*;
+
+#[cfg(not(unix))]
+mod other;
+
+#[cfg(not(unix))]
+pub use self::other::*;
@@ -0,0 +1,18 @@
+pub use remove_dir_all::remove_dir_all;
+use std::path::Path;
+use std::{fs, io};
+
+pub fn create_dir(path: &Path, _permissions: Option<&fs::Permissions>) -> io::Result<()> {
+    fs::create_dir(path)
+}
+
+// There's no way to refuse to replace the target atomically here, so check for it first.
+pub fn rename(old_path: &Path, new_path: &Path, overwrite: bool) -> io::Result<()> {
+    if !overwrite && fs::symlink_metadata(new_path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "destination path already exists",
+        ));
+    }
+    fs::rename(old_path, new_path)
+}
@@ -0,0 +1,274 @@
+use libc::{
+    c_char, c_int, c_uint, close, closedir, dirfd, fdopendir, mkdirat, open, openat, readdir,
+    unlinkat, AT_REMOVEDIR, DIR,
+};
+use libc::{EISDIR, ENOENT, ENOTDIR, EPERM};
+use libc::{
+    O_CLOEXEC, O_CREAT, O_DIRECTORY, O_EXCL, O_NOFOLLOW, O_RDONLY, O_RDWR, O_TRUNC, O_WRONLY,
+};
+use std::ffi::{CStr, CString};
+use std::fs::{self, File};
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::{Component, Path};
+
+use sys;
+use util;
+
+fn cvt_err(result: c_int) -> io::Result<c_int> {
+    if result == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result)
+    }
+}
+
+// Names are resolved relative to the directory handle, so they must not be able to escape it.
+// Symbolic links are only refused for the last component, everything before it has to have been
+// created inside the temporary directory.
+fn relative_cstr(name: &Path) -> io::Result<CString> {
+    if name.is_absolute() || name.components().any(|c| c == Component::ParentDir) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "path must be relative to the temporary directory",
+        ));
+    }
+    sys::cstr(name)
+}
+
+// Apply the mode at creation time so that the directory is never more accessible than
+// requested, then again because the umask may have removed bits from it. This goes by path:
+// opening the directory would need read permission, which the mode may not grant.
+pub fn create_dir(path: &Path, permissions: Option<&fs::Permissions>) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    let permissions = match permissions {
+        Some(permissions) => permissions,
+        None => return builder.create(path),
+    };
+    let mode = permissions.mode() & 0o7777;
+    builder.mode(mode).create(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).map_err(|e| {
+        let _ = fs::remove_dir(path);
+        e
+    })
+}
+
+pub fn rename(old_path: &Path, new_path: &Path, overwrite: bool) -> io::Result<()> {
+    if overwrite {
+        fs::rename(old_path, new_path)
+    } else {
+        rename_noclobber(old_path, new_path)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn rename_noclobber(old_path: &Path, new_path: &Path) -> io::Result<()> {
+    match sys::rename_noreplace(old_path, new_path) {
+        Err(ref e) if sys::is_unsupported(e) => rename_over_placeholder(old_path, new_path),
+        result => result,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn rename_noclobber(old_path: &Path, new_path: &Path) -> io::Result<()> {
+    rename_over_placeholder(old_path, new_path)
+}
+
+// Claim the target with an empty directory, which fails if anything exists there, and then
+// replace it. If something is put into the placeholder in between, the rename fails instead
+// of replacing it.
+fn rename_over_placeholder(old_path: &Path, new_path: &Path) -> io::Result<()> {
+    fs::create_dir(new_path)?;
+    fs::rename(old_path, new_path).map_err(|e| {
+        let _ = fs::remove_dir(new_path);
+        e
+    })
+}
+
+fn open_path(path: &Path, flags: c_int) -> io::Result<File> {
+    let path = sys::cstr(path)?;
+    unsafe {
+        let fd = cvt_err(open(
+            path.as_ptr() as *const c_char,
+            flags | O_CLOEXEC | O_DIRECTORY | O_RDONLY,
+        ))?;
+        Ok(FromRawFd::from_raw_fd(fd))
+    }
+}
+
+pub fn open_dir(path: &Path) -> io::Result<File> {
+    open_path(path, O_NOFOLLOW)
+}
+
+fn open_at(dir: &File, name: &Path, flags: c_int, mode: u32) -> io::Result<File> {
+    let name = relative_cstr(name)?;
+    unsafe {
+        let fd = cvt_err(openat(
+            dir.as_raw_fd(),
+            name.as_ptr() as *const c_char,
+            flags | O_CLOEXEC | O_NOFOLLOW,
+            mode as c_uint,
+        ))?;
+        Ok(FromRawFd::from_raw_fd(fd))
+    }
+}
+
+pub fn create_file_at(dir: &File, name: &Path) -> io::Result<File> {
+    open_at(dir, name, O_CREAT | O_TRUNC | O_WRONLY, 0o666)
+}
+
+pub fn create_named_at(
+    dir: &File,
+    name: &Path,
+    permissions: Option<&fs::Permissions>,
+) -> io::Result<File> {
+    let mode = permissions.map(|p| p.mode() & 0o7777).unwrap_or(0o600);
+    let file = open_at(dir, name, O_CREAT | O_EXCL | O_RDWR, mode)?;
+    if permissions.is_some() {
+        // Like `create_dir`, undo the umask.
+        if let Err(e) = file.set_permissions(fs::Permissions::from_mode(mode)) {
+            let _ = remove_at(dir, name);
+            return Err(e);
+        }
+    }
+    Ok(file)
+}
+
+pub fn open_file_at(dir: &File, name: &Path) -> io::Result<File> {
+    open_at(dir, name, O_RDONLY, 0)
+}
+
+pub fn create_dir_at(dir: &File, name: &Path) -> io::Result<()> {
+    let name = relative_cstr(name)?;
+    unsafe {
+        cvt_err(mkdirat(
+            dir.as_raw_fd(),
+            name.as_ptr() as *const c_char,
+            0o777,
+        ))?;
+    }
+    Ok(())
+}
+
+pub fn remove_at(dir: &File, name: &Path) -> io::Result<()> {
+    let name = relative_cstr(name)?;
+    unsafe {
+        let err = match cvt_err(unlinkat(dir.as_raw_fd(), name.as_ptr() as *const c_char, 0)) {
+            Ok(_) => return Ok(()),
+            Err(err) => err,
+        };
+        // Linux reports `EISDIR` when unlinking a directory, other platforms `EPERM`.
+        match err.raw_os_error() {
+            Some(EISDIR) | Some(EPERM) => {}
+            _ => return Err(err),
+        }
+        match cvt_err(unlinkat(
+            dir.as_raw_fd(),
+            name.as_ptr() as *const c_char,
+            AT_REMOVEDIR,
+        )) {
+            Ok(_) => Ok(()),
+            Err(ref e) if e.raw_os_error() == Some(ENOTDIR) => Err(err),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// Unlike walking the tree by path, every directory is opened relative to its parent's
+// descriptor without following symbolic links. Replacing a directory with a symbolic link
+// while it's being deleted therefore can't make us delete anything outside of it.
+pub fn remove_dir_all(path: &Path) -> io::Result<()> {
+    let name = match path.file_name() {
+        Some(name) => sys::cstr(Path::new(name))?,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "path has no file name",
+            ))
+        }
+    };
+    // The parent is the directory the temporary directory was created in, so it may be a
+    // symbolic link.
+    let parent = open_path(util::parent_dir(path), 0)?;
+    remove_dir_at(parent.as_raw_fd(), &name)
+}
+
+struct Dir(*mut DIR);
+
+impl Drop for Dir {
+    fn drop(&mut self) {
+        unsafe {
+            closedir(self.0);
+        }
+    }
+}
+
+fn open_dir_at(parent: c_int, name: &CStr) -> io::Result<Dir> {
+    unsafe {
+        let fd = cvt_err(openat(
+            parent,
+            name.as_ptr(),
+            O_CLOEXEC | O_DIRECTORY | O_NOFOLLOW | O_RDONLY,
+        ))?;
+        let dir = fdopendir(fd);
+        if dir.is_null() {
+            let err = io::Error::last_os_error();
+            close(fd);
+            return Err(err);
+        }
+        Ok(Dir(dir))
+    }
+}
+
+// Collect the names up front rather than deleting while iterating, as it's unspecified whether
+// `readdir` skips entries when the directory is modified. A failing `readdir` looks like the
+// end of the directory, but then removing the directory itself fails.
+fn read_names(dir: &Dir) -> Vec<CString> {
+    let mut names = Vec::new();
+    loop {
+        let name = unsafe {
+            let entry = readdir(dir.0);
+            if entry.is_null() {
+                break;
+            }
+            CStr::from_ptr((*entry).d_name.as_ptr())
+        };
+        match name.to_bytes() {
+            b"." | b".." => {}
+            _ => names.push(name.to_owned()),
+        }
+    }
+    names
+}
+
+fn remove_dir_at(parent: c_int, name: &CStr) -> io::Result<()> {
+    {
+        let dir = open_dir_at(parent, name)?;
+        let fd = unsafe { dirfd(dir.0) };
+        for entry in read_names(&dir) {
+            remove_entry_at(fd, &entry)?;
+        }
+    }
+    unsafe {
+        cvt_err(unlinkat(parent, name.as_ptr(), AT_REMOVEDIR))?;
+    }
+    Ok(())
+}
+
+fn remove_entry_at(parent: c_int, name: &CStr) -> io::Result<()> {
+    let err = match cvt_err(unsafe { unlinkat(parent, name.as_ptr(), 0) }) {
+        Ok(_) => return Ok(()),
+        Err(err) => err,
+    };
+    match err.raw_os_error() {
+        // Linux reports `EISDIR` when unlinking a directory, other platforms `EPERM`.
+        Some(EISDIR) | Some(EPERM) => match remove_dir_at(parent, name) {
+            Err(ref e) if e.raw_os_error() == Some(ENOTDIR) => Err(err),
+            res => res,
+        },
+        // Someone else has already deleted it.
+        Some(ENOENT) => Ok(()),
+        _ => Err(err),
+    }
+}
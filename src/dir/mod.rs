@@ -0,0 +1,827 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[cfg(unix)]
+use std::fs::File;
+use std::path::{self, Path, PathBuf};
+use std::{error, fmt, fs, io};
+
+use cleanup;
+#[cfg(target_os = "linux")]
+use sys;
+use util;
+use Builder;
+
+mod imp;
+
+pub use self::imp::remove_dir_all;
+
+/// Create a new temporary directory.
+///
+/// The `tempdir` function creates a directory in the file system
+/// and returns a [`TempDir`].
+/// The directory will be automatically deleted when the `TempDir`s
+/// desctructor is run.
+///
+/// # Resource Leaking
+///
+/// See [the resource leaking][resource-leaking] docs on `TempDir`.
+///
+/// # Errors
+///
+/// If the directory can not be created, `Err` is returned.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// use tempfile::tempdir;
+/// use std::fs::File;
+/// use std::io::{self, Write};
+///
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// // Create a directory inside of `std::env::temp_dir()`
+/// let dir = tempdir()?;
+///
+/// let file_path = dir.path().join("my-temporary-note.txt");
+/// let mut file = File::create(file_path)?;
+/// writeln!(file, "Brian was here. Briefly.")?;
+///
+/// // `tmp_dir` goes out of scope, the directory as well as
+/// // `tmp_file` will be deleted here.
+/// drop(file);
+/// dir.close()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`TempDir`]: struct.TempDir.html
+/// [resource-leaking]: struct.TempDir.html#resource-leaking
+pub fn tempdir() -> io::Result<TempDir> {
+    TempDir::new()
+}
+
+/// Create a new temporary directory.
+///
+/// The `tempdir` function creates a directory in the file system
+/// and returns a [`TempDir`].
+/// The directory will be automatically deleted when the `TempDir`s
+/// desctructor is run.
+///
+/// # Resource Leaking
+///
+/// See [the resource leaking][resource-leaking] docs on `TempDir`.
+///
+/// # Errors
+///
+/// If the directory can not be created, `Err` is returned.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// use tempfile::tempdir;
+/// use std::fs::File;
+/// use std::io::{self, Write};
+///
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// // Create a directory inside of `std::env::temp_dir()`,
+/// let dir = tempdir()?;
+///
+/// let file_path = dir.path().join("my-temporary-note.txt");
+/// let mut file = File::create(file_path)?;
+/// writeln!(file, "Brian was here. Briefly.")?;
+///
+/// // `tmp_dir` goes out of scope, the directory as well as
+/// // `tmp_file` will be deleted here.
+/// drop(file);
+/// dir.close()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`TempDir`]: struct.TempDir.html
+/// [resource-leaking]: struct.TempDir.html#resource-leaking
+pub fn tempdir_in<P: AsRef<Path>>(dir: P) -> io::Result<TempDir> {
+    TempDir::new_in(dir)
+}
+
+/// A directory in the filesystem that is automatically deleted when
+/// it goes out of scope.
+///
+/// The [`TempDir`] type creates a directory on the file system that
+/// is deleted once it goes out of scope. At construction, the
+/// `TempDir` creates a new directory with a randomly generated name,
+/// and with a prefix of your choosing.
+///
+/// The default constructor, [`TempDir::new()`], creates directories in
+/// the location returned by [`env::temp_dir()`], but `TempDir`
+/// can be configured to manage a temporary directory in any location
+/// by constructing with a [`Builder`].
+///
+/// After creating a `TempDir`, work with the file system by doing
+/// standard [`std::fs`] file system operations on its [`Path`],
+/// which can be retrieved with [`TempDir::path()`]. Once the `TempDir`
+/// value is dropped, the directory at the path will be deleted, along
+/// with any files and directories it contains. It is your responsibility
+/// to ensure that no further file system operations are attempted
+/// inside the temporary directory once it has been deleted.
+///
+/// On Unix, the directory is deleted without following symbolic links: every subdirectory is
+/// opened relative to its parent, so replacing one with a symbolic link while the directory is
+/// being deleted can't cause files outside of it to be deleted.
+///
+/// # Resource Leaking
+///
+/// Various platform-specific conditions may cause `TempDir` to fail
+/// to delete the underlying directory. It's important to ensure that
+/// handles (like [`File`] and [`ReadDir`]) to files inside the
+/// directory are dropped before the `TempDir` goes out of scope. The
+/// `TempDir` destructor will silently ignore any errors in deleting
+/// the directory; to instead handle errors call [`TempDir::close()`].
+///
+/// Note that if the program exits before the `TempDir` destructor is
+/// run, such as via [`std::process::exit()`], by segfaulting, or by
+/// receiving a signal like `SIGINT`, then the temporary directory
+/// will not be deleted. On Unix, [`cleanup_on_exit()`] deletes it in
+/// the first and last case.
+///
+/// # Examples
+///
+/// Create a temporary directory with a generated name:
+///
+/// ```
+/// use std::fs::File;
+/// use std::io::Write;
+/// use tempfile::TempDir;
+///
+/// # use std::io;
+/// # fn run() -> Result<(), io::Error> {
+/// // Create a directory inside of `std::env::temp_dir()`
+/// let tmp_dir = TempDir::new()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Create a temporary directory with a prefix in its name:
+///
+/// ```
+/// use std::fs::File;
+/// use std::io::Write;
+/// use tempfile::Builder;
+///
+/// # use std::io;
+/// # fn run() -> Result<(), io::Error> {
+/// // Create a directory inside of `std::env::temp_dir()`,
+/// // whose name will begin with 'example'.
+/// let tmp_dir = Builder::new().prefix("example").tempdir()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`File`]: http://doc.rust-lang.org/std/fs/struct.File.html
+/// [`Path`]: http://doc.rust-lang.org/std/path/struct.Path.html
+/// [`ReadDir`]: http://doc.rust-lang.org/std/fs/struct.ReadDir.html
+/// [`Builder`]: struct.Builder.html
+/// [`TempDir::close()`]: struct.TempDir.html#method.close
+/// [`cleanup_on_exit()`]: fn.cleanup_on_exit.html
+/// [`TempDir::new()`]: struct.TempDir.html#method.new
+/// [`TempDir::path()`]: struct.TempDir.html#method.path
+/// [`TempDir`]: struct.TempDir.html
+/// [`env::temp_dir()`]: env/fn.temp_dir.html
+/// [`std::fs`]: http://doc.rust-lang.org/std/fs/index.html
+/// [`std::process::exit()`]: http://doc.rust-lang.org/std/process/fn.exit.html
+pub struct TempDir {
+    path: Option<PathBuf>,
+    keep: bool,
+    #[cfg(unix)]
+    handle: Option<File>,
+}
+
+impl TempDir {
+    /// Attempts to make a temporary directory inside of [`env::temp_dir()`].
+    ///
+    /// See [`Builder`] for more configuration.
+    ///
+    /// The directory and everything inside it will be automatically deleted
+    /// once the returned `TempDir` is destroyed.
+    ///
+    /// # Errors
+    ///
+    /// If the directory can not be created, `Err` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::Write;
+    /// use tempfile::TempDir;
+    ///
+    /// # use std::io;
+    /// # fn run() -> Result<(), io::Error> {
+    /// // Create a directory inside of `std::env::temp_dir()`
+    /// let tmp_dir = TempDir::new()?;
+    ///
+    /// let file_path = tmp_dir.path().join("my-temporary-note.txt");
+    /// let mut tmp_file = File::create(file_path)?;
+    /// writeln!(tmp_file, "Brian was here. Briefly.")?;
+    ///
+    /// // `tmp_dir` goes out of scope, the directory as well as
+    /// // `tmp_file` will be deleted here.
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Builder`]: struct.Builder.html
+    /// [`env::temp_dir()`]: env/fn.temp_dir.html
+    pub fn new() -> io::Result<TempDir> {
+        Builder::new().tempdir()
+    }
+
+    /// Attempts to make a temporary directory inside of `tmpdir`
+    /// whose name will have the prefix `prefix`. The directory and
+    /// everything inside it will be automatically deleted once the
+    /// returned `TempDir` is destroyed.
+    ///
+    /// # Errors
+    ///
+    /// If the directory can not be created, `Err` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::{self, File};
+    /// use std::io::Write;
+    /// use tempfile::TempDir;
+    ///
+    /// # use std::io;
+    /// # fn run() -> Result<(), io::Error> {
+    /// // Create a directory inside of the current directory
+    /// let tmp_dir = TempDir::new_in(".")?;
+    /// let file_path = tmp_dir.path().join("my-temporary-note.txt");
+    /// let mut tmp_file = File::create(file_path)?;
+    /// writeln!(tmp_file, "Brian was here. Briefly.")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_in<P: AsRef<Path>>(dir: P) -> io::Result<TempDir> {
+        Builder::new().tempdir_in(dir)
+    }
+
+    /// Accesses the [`Path`] to the temporary directory.
+    ///
+    /// [`Path`]: http://doc.rust-lang.org/std/path/struct.Path.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tempfile::TempDir;
+    ///
+    /// # use std::io;
+    /// # fn run() -> Result<(), io::Error> {
+    /// let tmp_path;
+    ///
+    /// {
+    ///    let tmp_dir = TempDir::new()?;
+    ///    tmp_path = tmp_dir.path().to_owned();
+    ///
+    ///    // Check that the temp directory actually exists.
+    ///    assert!(tmp_path.exists());
+    ///
+    ///    // End of `tmp_dir` scope, directory will be deleted
+    /// }
+    ///
+    /// // Temp directory should be deleted by now
+    /// assert_eq!(tmp_path.exists(), false);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn path(&self) -> &path::Path {
+        self.path.as_ref().unwrap()
+    }
+
+    /// Persist the temporary directory to disk, returning the [`PathBuf`] where it is located.
+    ///
+    /// This consumes the [`TempDir`] without deleting directory on the filesystem, meaning that
+    /// the directory will no longer be automatically deleted.
+    ///
+    /// [`TempDir`]: struct.TempDir.html
+    /// [`PathBuf`]: http://doc.rust-lang.org/std/path/struct.PathBuf.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs;
+    /// use tempfile::TempDir;
+    ///
+    /// # use std::io;
+    /// # fn run() -> Result<(), io::Error> {
+    /// let tmp_dir = TempDir::new()?;
+    ///
+    /// // Persist the temporary directory to disk,
+    /// // getting the path where it is.
+    /// let tmp_path = tmp_dir.into_path();
+    ///
+    /// // Delete the temporary directory ourselves.
+    /// fs::remove_dir_all(tmp_path)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_path(mut self) -> PathBuf {
+        let path = self.path.take().unwrap();
+        cleanup::unregister(&path);
+        path
+    }
+
+    /// Move the temporary directory and everything in it to `new_path`, replacing an empty
+    /// directory there.
+    ///
+    /// The directory is renamed, so it appears at `new_path` with all of its contents at once.
+    /// On success, it's no longer deleted. If this fails, the `TempDir` is returned in the
+    /// [`DirPersistError`] and is still deleted when dropped.
+    ///
+    /// Note: Directories cannot be persisted across filesystems. On Unix, an existing
+    /// directory at `new_path` is only replaced if it's empty. On Windows, existing directories
+    /// are never replaced.
+    ///
+    /// # Errors
+    ///
+    /// If the directory cannot be moved to the new location, `Err` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tempfile;
+    /// # use std::io::{self, Write};
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// use std::fs::File;
+    /// use tempfile::Builder;
+    ///
+    /// // Stage the new version next to the old one, so it's on the same filesystem.
+    /// let staging = Builder::new().prefix(".staging").tempdir_in("/opt/app")?;
+    /// let mut config = File::create(staging.path().join("config.toml"))?;
+    /// writeln!(config, "answer = 42")?;
+    ///
+    /// staging.persist("/opt/app/1.2.0")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`DirPersistError`]: struct.DirPersistError.html
+    pub fn persist<P: AsRef<Path>>(self, new_path: P) -> Result<(), DirPersistError> {
+        self.persist_inner(new_path.as_ref(), true)
+    }
+
+    /// Move the temporary directory and everything in it to `new_path` iff nothing exists
+    /// there.
+    ///
+    /// Like [`TempDir::persist()`], but fails if a file or directory exists at `new_path`. On
+    /// Linux, this uses `renameat2` with `RENAME_NOREPLACE` if the kernel and filesystem
+    /// support it. Otherwise, on Unix, an empty directory is created at `new_path` first and
+    /// then replaced, so the target is never clobbered but may briefly appear empty.
+    ///
+    /// # Errors
+    ///
+    /// If the directory cannot be moved to the new location or something already exists
+    /// there, `Err` is returned.
+    ///
+    /// [`TempDir::persist()`]: #method.persist
+    pub fn persist_noclobber<P: AsRef<Path>>(self, new_path: P) -> Result<(), DirPersistError> {
+        self.persist_inner(new_path.as_ref(), false)
+    }
+
+    /// Atomically swap the temporary directory with the existing directory at `target`.
+    ///
+    /// Afterwards, the contents of the temporary directory are at `target`, and the
+    /// directory that was there is at the temporary path. It's returned as a new `TempDir`,
+    /// so it's deleted when dropped unless it's kept, and can be inspected or swapped back to
+    /// roll back. Unlike [`TempDir::persist()`], this can replace non-empty directories, and
+    /// there's no moment at which `target` doesn't exist.
+    ///
+    /// The returned `TempDir` has no directory handle, even if this one had one: the handle
+    /// refers to the directory that is now at `target`.
+    ///
+    /// This uses `renameat2` with `RENAME_EXCHANGE` and is only available on Linux. Only the
+    /// exchange itself is atomic: `target` is checked to be a directory beforehand, but if
+    /// another process replaces it in between, whatever is at `target` then is swapped.
+    ///
+    /// # Errors
+    ///
+    /// If `target` isn't a directory, or the kernel or filesystem doesn't support exchanging
+    /// paths, or the directories can't be swapped for another reason, `Err` is returned and
+    /// nothing is changed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// use tempfile::Builder;
+    ///
+    /// let staging = Builder::new().prefix(".assets").tempdir_in("/srv/www")?;
+    /// // ... build the new asset tree in `staging` ...
+    ///
+    /// let previous = staging.persist_exchange("/srv/www/assets")?;
+    /// // `previous` holds the old assets and deletes them when dropped.
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`TempDir::persist()`]: #method.persist
+    #[cfg(target_os = "linux")]
+    pub fn persist_exchange<P: AsRef<Path>>(
+        mut self,
+        target: P,
+    ) -> Result<TempDir, DirPersistError> {
+        let target = target.as_ref();
+        // Best effort: the kernel happily exchanges a directory and a file.
+        let result = fs::symlink_metadata(target).and_then(|metadata| {
+            if !metadata.is_dir() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "target is not a directory",
+                ));
+            }
+            sys::rename_exchange(self.path(), target)
+        });
+        match result {
+            Ok(()) => Ok(TempDir {
+                path: self.path.take(),
+                keep: self.keep,
+                handle: None,
+            }),
+            Err(error) => Err(DirPersistError { error, dir: self }),
+        }
+    }
+
+    fn persist_inner(mut self, new_path: &Path, overwrite: bool) -> Result<(), DirPersistError> {
+        match imp::rename(self.path(), new_path, overwrite) {
+            Ok(()) => {
+                cleanup::unregister(self.path());
+                // Prevent the Drop impl from removing the moved directory.
+                self.path = None;
+                Ok(())
+            }
+            Err(error) => Err(DirPersistError { error, dir: self }),
+        }
+    }
+
+    /// Keep the temporary directory instead of deleting it when the `TempDir` is dropped or
+    /// closed.
+    ///
+    /// Unlike [`TempDir::into_path()`], this doesn't require taking ownership of the
+    /// `TempDir`, so it can be used without changing the code that uses the directory.
+    /// Setting the `TEMPFILE_KEEP` environment variable to a value other than `0` keeps every
+    /// temporary file and directory and reports their paths on stderr.
+    ///
+    /// See also [`Builder::keep()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tempfile::TempDir;
+    ///
+    /// # use std::io;
+    /// # fn run() -> Result<(), io::Error> {
+    /// let mut tmp_dir = TempDir::new()?;
+    ///
+    /// // Leave the directory behind so it can be inspected.
+    /// tmp_dir.keep(true);
+    /// # ::std::fs::remove_dir(tmp_dir.path())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`TempDir::into_path()`]: #method.into_path
+    /// [`Builder::keep()`]: struct.Builder.html#method.keep
+    pub fn keep(&mut self, keep: bool) {
+        self.keep = keep;
+        if keep {
+            cleanup::unregister(self.path());
+        } else {
+            cleanup::register_dir(self.path());
+        }
+    }
+
+    /// Create a file inside the temporary directory, truncating it if it already exists.
+    ///
+    /// This is the equivalent of [`File::create()`], except that `name` is resolved relative
+    /// to the directory handle with `openat`, and that the last component of `name` must not be
+    /// a symbolic link. Requires the `TempDir` to have been created with
+    /// [`Builder::dir_handle()`].
+    ///
+    /// # Errors
+    ///
+    /// If the `TempDir` has no directory handle, `name` isn't a relative path without `..`
+    /// components, or the file can not be created, `Err` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io::{self, Write};
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// use tempfile::Builder;
+    ///
+    /// let tmp_dir = Builder::new().dir_handle(true).tempdir()?;
+    /// let mut tmp_file = tmp_dir.create_file("my-temporary-note.txt")?;
+    /// writeln!(tmp_file, "Brian was here. Briefly.")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`File::create()`]: http://doc.rust-lang.org/std/fs/struct.File.html#method.create
+    /// [`Builder::dir_handle()`]: struct.Builder.html#method.dir_handle
+    #[cfg(unix)]
+    pub fn create_file<P: AsRef<Path>>(&self, name: P) -> io::Result<File> {
+        imp::create_file_at(self.handle()?, name.as_ref())
+    }
+
+    /// Open a file inside the temporary directory in read-only mode.
+    ///
+    /// This is the equivalent of [`File::open()`], resolving `name` like
+    /// [`TempDir::create_file()`].
+    ///
+    /// # Errors
+    ///
+    /// If the `TempDir` has no directory handle, `name` isn't a relative path without `..`
+    /// components, or the file can not be opened, `Err` is returned.
+    ///
+    /// [`File::open()`]: http://doc.rust-lang.org/std/fs/struct.File.html#method.open
+    /// [`TempDir::create_file()`]: #method.create_file
+    #[cfg(unix)]
+    pub fn open<P: AsRef<Path>>(&self, name: P) -> io::Result<File> {
+        imp::open_file_at(self.handle()?, name.as_ref())
+    }
+
+    /// Create a directory inside the temporary directory.
+    ///
+    /// This is the equivalent of [`fs::create_dir()`], resolving `name` like
+    /// [`TempDir::create_file()`].
+    ///
+    /// # Errors
+    ///
+    /// If the `TempDir` has no directory handle, `name` isn't a relative path without `..`
+    /// components, or the directory can not be created, `Err` is returned.
+    ///
+    /// [`fs::create_dir()`]: http://doc.rust-lang.org/std/fs/fn.create_dir.html
+    /// [`TempDir::create_file()`]: #method.create_file
+    #[cfg(unix)]
+    pub fn create_dir<P: AsRef<Path>>(&self, name: P) -> io::Result<()> {
+        imp::create_dir_at(self.handle()?, name.as_ref())
+    }
+
+    /// Remove a file or an empty directory inside the temporary directory.
+    ///
+    /// `name` is resolved like [`TempDir::create_file()`]. If it's a symbolic link, the link
+    /// itself is removed.
+    ///
+    /// # Errors
+    ///
+    /// If the `TempDir` has no directory handle, `name` isn't a relative path without `..`
+    /// components, or the file or directory can not be removed, `Err` is returned.
+    ///
+    /// [`TempDir::create_file()`]: #method.create_file
+    #[cfg(unix)]
+    pub fn remove<P: AsRef<Path>>(&self, name: P) -> io::Result<()> {
+        imp::remove_at(self.handle()?, name.as_ref())
+    }
+
+    /// Create an anonymous temporary file inside the temporary directory.
+    ///
+    /// Like [`tempfile_in()`], but the file is created and unlinked relative to the directory
+    /// handle.
+    ///
+    /// # Errors
+    ///
+    /// If the `TempDir` has no directory handle or the file can not be created, `Err` is
+    /// returned.
+    ///
+    /// [`tempfile_in()`]: fn.tempfile_in.html
+    #[cfg(unix)]
+    pub fn tempfile(&self) -> io::Result<File> {
+        let handle = self.handle()?;
+        let (file, name) = util::create_helper_named(self.path(), &Builder::new(), |name, _| {
+            imp::create_named_at(handle, name, None).map(|file| (file, name.to_owned()))
+        })?;
+        // Like `tempfile_in`, don't care whether the name has already been removed.
+        let _ = imp::remove_at(handle, &name);
+        Ok(file)
+    }
+
+    /// Closes and removes the temporary directory, returing a `Result`.
+    ///
+    /// Although `TempDir` removes the directory on drop, in the destructor
+    /// any errors are ignored. To detect errors cleaning up the temporary
+    /// directory, call `close` instead.
+    ///
+    /// # Errors
+    ///
+    /// This function may return a variety of [`std::io::Error`]s that result from deleting
+    /// the files and directories contained with the temporary directory,
+    /// as well as from deleting the temporary directory itself. These errors
+    /// may be platform specific.
+    ///
+    /// [`std::io::Error`]: http://doc.rust-lang.org/std/io/struct.Error.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::Write;
+    /// use tempfile::TempDir;
+    ///
+    /// # use std::io;
+    /// # fn run() -> Result<(), io::Error> {
+    /// // Create a directory inside of `std::env::temp_dir()`, named with
+    /// // the prefix, "example".
+    /// let tmp_dir = TempDir::new()?;
+    /// let file_path = tmp_dir.path().join("my-temporary-note.txt");
+    /// let mut tmp_file = File::create(file_path)?;
+    /// writeln!(tmp_file, "Brian was here. Briefly.")?;
+    ///
+    /// // By closing the `TempDir` explicitly we can check that it has
+    /// // been deleted successfully. If we don't close it explicitly,
+    /// // the directory will still be deleted when `tmp_dir` goes out
+    /// // of scope, but we won't know whether deleting the directory
+    /// // succeeded.
+    /// drop(tmp_file);
+    /// tmp_dir.close()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn close(mut self) -> io::Result<()> {
+        cleanup::unregister(self.path());
+        let result = if util::should_keep(self.keep, self.path()) {
+            Ok(())
+        } else {
+            imp::remove_dir_all(self.path())
+        };
+
+        // Prevent the Drop impl from removing the dir a second time.
+        self.path = None;
+
+        result
+    }
+}
+
+/// Error returned when persisting a temporary directory fails.
+#[derive(Debug)]
+pub struct DirPersistError {
+    /// The underlying IO error.
+    pub error: io::Error,
+    /// The temporary directory that couldn't be persisted.
+    pub dir: TempDir,
+}
+
+impl From<DirPersistError> for io::Error {
+    #[inline]
+    fn from(error: DirPersistError) -> io::Error {
+        error.error
+    }
+}
+
+impl From<DirPersistError> for TempDir {
+    #[inline]
+    fn from(error: DirPersistError) -> TempDir {
+        error.dir
+    }
+}
+
+impl fmt::Display for DirPersistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to persist temporary directory: {}", self.error)
+    }
+}
+
+impl error::Error for DirPersistError {
+    fn description(&self) -> &str {
+        "failed to persist temporary directory"
+    }
+    fn cause(&self) -> Option<&error::Error> {
+        Some(&self.error)
+    }
+}
+
+impl TempDir {
+    #[cfg(unix)]
+    fn handle(&self) -> io::Result<&File> {
+        self.handle.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "temporary directory was created without a directory handle",
+            )
+        })
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl fmt::Debug for TempDir {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TempDir")
+            .field("path", &self.path())
+            .finish()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        // Path is `None` if `close()` or `into_path()` has been called.
+        if let Some(ref p) = self.path {
+            cleanup::unregister(p);
+            if !util::should_keep(self.keep, p) {
+                let _ = imp::remove_dir_all(p);
+            }
+        }
+    }
+}
+
+// pub(crate)
+#[cfg(unix)]
+pub fn create(
+    path: PathBuf,
+    permissions: Option<&fs::Permissions>,
+    keep: bool,
+    dir_handle: bool,
+) -> io::Result<TempDir> {
+    imp::create_dir(&path, permissions)?;
+    if !keep {
+        cleanup::register_dir(&path);
+    }
+    let mut dir = TempDir {
+        path: Some(path),
+        keep,
+        handle: None,
+    };
+    // If the handle can't be opened, dropping `dir` removes the directory again.
+    if dir_handle {
+        dir.handle = Some(imp::open_dir(dir.path())?);
+    }
+    Ok(dir)
+}
+
+// pub(crate)
+#[cfg(not(unix))]
+pub fn create(
+    path: PathBuf,
+    permissions: Option<&fs::Permissions>,
+    keep: bool,
+    _dir_handle: bool,
+) -> io::Result<TempDir> {
+    imp::create_dir(&path, permissions)?;
+    if !keep {
+        cleanup::register_dir(&path);
+    }
+    Ok(TempDir {
+        path: Some(path),
+        keep,
+    })
+}
+
+// pub(crate)
+#[cfg(unix)]
+pub fn create_named_at(
+    dir: &TempDir,
+    name: &Path,
+    permissions: Option<&fs::Permissions>,
+) -> io::Result<File> {
+    imp::create_named_at(dir.handle()?, name, permissions)
+}
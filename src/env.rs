@@ -0,0 +1,86 @@
+//! Configure where temporary files and directories are created by default.
+//!
+//! [`tempfile()`], [`NamedTempFile::new()`], [`tempdir()`] and the [`Builder`] methods without
+//! an explicit directory create their files in [`temp_dir()`]. It returns, in order of
+//! precedence:
+//!
+//! 1. the directory set with [`override_temp_dir()`],
+//! 2. the value of the `TEMPFILE_TMPDIR` environment variable, if it's set and not empty,
+//! 3. [`std::env::temp_dir()`].
+//!
+//! Unlike setting `TMPDIR`, this only affects this crate, including its use by other
+//! libraries in the same process, and isn't inherited by child processes.
+//!
+//! [`tempfile()`]: ../fn.tempfile.html
+//! [`NamedTempFile::new()`]: ../struct.NamedTempFile.html#method.new
+//! [`tempdir()`]: ../fn.tempdir.html
+//! [`Builder`]: ../struct.Builder.html
+//! [`temp_dir()`]: fn.temp_dir.html
+//! [`override_temp_dir()`]: fn.override_temp_dir.html
+//! [`std::env::temp_dir()`]: https://doc.rust-lang.org/std/env/fn.temp_dir.html
+
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+// Once set, the override is never freed so that `temp_dir` can read it without locking.
+static OVERRIDE: AtomicPtr<PathBuf> = AtomicPtr::new(ptr::null_mut());
+
+/// Set the default directory for temporary files and directories for the whole process.
+///
+/// The override can only be set once. It takes precedence over the `TEMPFILE_TMPDIR`
+/// environment variable.
+///
+/// # Errors
+///
+/// If the override has already been set, an error of kind `AlreadyExists` is returned and the
+/// override is left unchanged.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate tempfile;
+/// # use std::io;
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// tempfile::env::override_temp_dir("/scratch/tmp")?;
+///
+/// // Created in `/scratch/tmp`.
+/// let file = tempfile::tempfile()?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn override_temp_dir<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let path = Box::into_raw(Box::new(path.as_ref().to_owned()));
+    if OVERRIDE
+        .compare_exchange(ptr::null_mut(), path, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        drop(unsafe { Box::from_raw(path) });
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "the temporary directory has already been overridden",
+        ));
+    }
+    Ok(())
+}
+
+/// The default directory for temporary files and directories.
+///
+/// See [the module documentation](index.html) for how it's determined.
+pub fn temp_dir() -> PathBuf {
+    let path = OVERRIDE.load(Ordering::SeqCst);
+    if !path.is_null() {
+        return unsafe { (*path).clone() };
+    }
+    match env::var_os("TEMPFILE_TMPDIR") {
+        Some(ref dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => env::temp_dir(),
+    }
+}
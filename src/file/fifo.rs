@@ -0,0 +1,178 @@
+use libc::{self, c_char};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use super::TempPath;
+use cleanup;
+use env;
+use sys::cstr;
+use Builder;
+
+/// A named pipe (FIFO) that is deleted when dropped.
+///
+/// This is useful for streaming data to or from programs that only accept file paths. The
+/// FIFO is created with `mkfifo` under a unique name, like a [`NamedTempFile`], and removed
+/// when the `NamedTempFifo` is dropped or closed, unless it's kept. Use
+/// [`NamedTempFifo::open_read()`] and [`NamedTempFifo::open_write()`] to open its ends.
+///
+/// See [`Builder::fifo()`] for more configuration.
+///
+/// This is only available on Unix.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// # use std::io;
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// use std::fs::OpenOptions;
+/// use std::io::{Read, Write};
+/// use std::thread;
+/// use tempfile::NamedTempFifo;
+///
+/// let fifo = NamedTempFifo::new()?;
+///
+/// // Usually, the path is passed to another program instead.
+/// let path = fifo.path().to_owned();
+/// let writer = thread::spawn(move || -> io::Result<()> {
+///     let mut input = OpenOptions::new().write(true).open(path)?;
+///     input.write_all(b"Brian was here. Briefly.")
+/// });
+///
+/// let mut output = String::new();
+/// fifo.open_read()?.read_to_string(&mut output)?;
+/// writer.join().unwrap()?;
+/// assert_eq!(output, "Brian was here. Briefly.");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`NamedTempFile`]: struct.NamedTempFile.html
+/// [`NamedTempFifo::open_read()`]: #method.open_read
+/// [`NamedTempFifo::open_write()`]: #method.open_write
+/// [`Builder::fifo()`]: struct.Builder.html#method.fifo
+pub struct NamedTempFifo {
+    path: TempPath,
+}
+
+impl fmt::Debug for NamedTempFifo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NamedTempFifo({:?})", self.path)
+    }
+}
+
+impl AsRef<Path> for NamedTempFifo {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl NamedTempFifo {
+    /// Create a new FIFO in [`env::temp_dir()`].
+    ///
+    /// # Errors
+    ///
+    /// If the FIFO can not be created, `Err` is returned.
+    ///
+    /// [`env::temp_dir()`]: env/fn.temp_dir.html
+    pub fn new() -> io::Result<NamedTempFifo> {
+        Builder::new().fifo_in(&env::temp_dir())
+    }
+
+    /// Create a new FIFO in the specified directory.
+    ///
+    /// # Errors
+    ///
+    /// If the FIFO can not be created, `Err` is returned.
+    pub fn new_in<P: AsRef<Path>>(dir: P) -> io::Result<NamedTempFifo> {
+        Builder::new().fifo_in(dir)
+    }
+
+    /// Get the FIFO's path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Open the read end of the FIFO.
+    ///
+    /// This blocks until the write end is opened, by this or another process.
+    ///
+    /// # Errors
+    ///
+    /// If the FIFO can not be opened, `Err` is returned.
+    pub fn open_read(&self) -> io::Result<File> {
+        OpenOptions::new().read(true).open(self.path())
+    }
+
+    /// Open the write end of the FIFO.
+    ///
+    /// This blocks until the read end is opened, by this or another process.
+    ///
+    /// # Errors
+    ///
+    /// If the FIFO can not be opened, `Err` is returned.
+    pub fn open_write(&self) -> io::Result<File> {
+        OpenOptions::new().write(true).open(self.path())
+    }
+
+    /// Keep the FIFO instead of deleting it when it's dropped or closed.
+    ///
+    /// See [`TempPath::keep()`](struct.TempPath.html#method.keep).
+    pub fn keep(&mut self, keep: bool) {
+        self.path.keep(keep);
+    }
+
+    /// Remove the FIFO.
+    ///
+    /// Use this if you want to detect errors in deleting it. Ends that are already open
+    /// remain usable.
+    ///
+    /// # Errors
+    ///
+    /// If the FIFO cannot be deleted, `Err` is returned.
+    pub fn close(self) -> io::Result<()> {
+        self.path.close()
+    }
+
+    /// Convert into the [`TempPath`] of the FIFO.
+    ///
+    /// [`TempPath`]: struct.TempPath.html
+    pub fn into_temp_path(self) -> TempPath {
+        self.path
+    }
+}
+
+// pub(crate)
+pub fn create_fifo(
+    path: PathBuf,
+    permissions: Option<&fs::Permissions>,
+    keep: bool,
+) -> io::Result<NamedTempFifo> {
+    let mode = permissions.map(|p| p.mode() & 0o7777).unwrap_or(0o600);
+    let c_path = cstr(&path)?;
+    if unsafe { libc::mkfifo(c_path.as_ptr() as *const c_char, mode as libc::mode_t) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // `mkfifo` masks the mode with the umask, so apply the requested permissions exactly.
+    if permissions.is_some() {
+        if let Err(e) = fs::set_permissions(&path, fs::Permissions::from_mode(mode)) {
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+    }
+    if !keep {
+        cleanup::register_file(&path);
+    }
+    Ok(NamedTempFifo {
+        path: TempPath { path, keep },
+    })
+}
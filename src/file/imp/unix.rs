@@ -1,12 +1,22 @@
+use file::PersistStrategy;
 #[cfg(not(target_os = "redox"))]
-use libc::{c_char, c_int, link, rename, unlink, O_CLOEXEC, O_CREAT, O_EXCL, O_RDWR};
+use libc::{c_char, c_int, c_uint, link, rename, unlink, O_CLOEXEC, O_CREAT, O_EXCL, O_RDWR};
+#[cfg(target_os = "linux")]
 use std::ffi::CString;
+#[cfg(target_os = "linux")]
+use std::ffi::OsStr;
 use std::fs::{self, File, OpenOptions};
 use std::io;
+#[cfg(any(target_os = "linux", target_os = "redox"))]
 use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
 use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "linux")]
+use sys;
+use sys::cstr;
 use util;
+use Builder;
 
 #[cfg(all(lfs_support, target_os = "linux"))]
 use libc::{fstat64 as fstat, open64 as open, stat64 as stat_t};
@@ -33,38 +43,60 @@ pub fn cvt_err(result: Result<usize, syscall::Error>) -> io::Result<usize> {
     result.map_err(|err| io::Error::from_raw_os_error(err.errno))
 }
 
-// Stolen from std.
-pub fn cstr(path: &Path) -> io::Result<CString> {
-    CString::new(path.as_os_str().as_bytes())
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contained a null"))
+// The mode is passed to `open` so that the file never exists with more permissions than
+// requested. `set_mode` then applies it exactly, ignoring the umask.
+fn mode(permissions: Option<&fs::Permissions>) -> u32 {
+    permissions.map(|p| p.mode() & 0o7777).unwrap_or(0o600)
+}
+
+// `open` only applies the mode after masking it with the umask, so set it again on the new file
+// to make sure the requested permissions are used as they are.
+fn fchmod(file: &File, permissions: Option<&fs::Permissions>) -> io::Result<()> {
+    match permissions {
+        Some(_) => file.set_permissions(fs::Permissions::from_mode(mode(permissions))),
+        None => Ok(()),
+    }
+}
+
+// Like `fchmod`, but removes the file at `path` if that fails.
+fn set_mode(file: File, path: &Path, permissions: Option<&fs::Permissions>) -> io::Result<File> {
+    match fchmod(&file, permissions) {
+        Ok(()) => Ok(file),
+        Err(e) => {
+            let _ = fs::remove_file(path);
+            Err(e)
+        }
+    }
 }
 
 #[cfg(not(target_os = "redox"))]
-pub fn create_named(path: &Path) -> io::Result<File> {
+pub fn create_named(path: &Path, permissions: Option<&fs::Permissions>) -> io::Result<File> {
     unsafe {
-        let path = cstr(path)?;
+        let c_path = cstr(path)?;
         let fd = cvt_err(open(
-            path.as_ptr() as *const c_char,
+            c_path.as_ptr() as *const c_char,
             O_CLOEXEC | O_EXCL | O_RDWR | O_CREAT,
-            0o600,
+            mode(permissions) as c_uint,
         ))?;
-        Ok(FromRawFd::from_raw_fd(fd))
+        let file: File = FromRawFd::from_raw_fd(fd);
+        set_mode(file, path, permissions)
     }
 }
 
 #[cfg(target_os = "redox")]
-pub fn create_named(path: PathBuf) -> io::Result<File> {
+pub fn create_named(path: &Path, permissions: Option<&fs::Permissions>) -> io::Result<File> {
     unsafe {
         let fd = cvt_err(open(
             path.as_os_str().as_bytes(),
-            O_CLOEXEC | O_EXCL | O_RDWR | O_CREAT | 0o600,
+            O_CLOEXEC | O_EXCL | O_RDWR | O_CREAT | mode(permissions) as usize,
         ))?;
-        Ok(FromRawFd::from_raw_fd(fd))
+        let file: File = FromRawFd::from_raw_fd(fd);
+        set_mode(file, path, permissions)
     }
 }
 
 fn create_unlinked(path: &Path) -> io::Result<File> {
-    let f = create_named(path)?;
+    let f = create_named(path, None)?;
     // don't care whether the path has already been unlinked,
     // but perhaps there are some IO error conditions we should send up?
     let _ = fs::remove_file(path);
@@ -87,15 +119,59 @@ pub fn create(dir: &Path) -> io::Result<File> {
     }
 }
 
+#[cfg(target_os = "linux")]
+pub fn create_linkable(dir: &Path, permissions: Option<&fs::Permissions>) -> io::Result<File> {
+    use libc::O_TMPFILE;
+    unsafe {
+        let path = cstr(dir)?;
+        // Unlike `create`, leave out `O_EXCL` so that the file can be linked into the
+        // filesystem later.
+        let fd = cvt_err(open(
+            path.as_ptr() as *const c_char,
+            O_CLOEXEC | O_TMPFILE | O_RDWR,
+            mode(permissions) as c_uint,
+        ))?;
+        // The file has no name yet, so there's nothing to remove if this fails.
+        let file: File = FromRawFd::from_raw_fd(fd);
+        fchmod(&file, permissions)?;
+        Ok(file)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn create_memfd(name: &OsStr) -> io::Result<File> {
+    use libc::{syscall, SYS_memfd_create, MFD_ALLOW_SEALING, MFD_CLOEXEC};
+
+    let name = CString::new(name.as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "name contained a null"))?;
+    // Called through `syscall` because older C libraries don't wrap `memfd_create`.
+    let fd = cvt_err(unsafe {
+        syscall(
+            SYS_memfd_create,
+            name.as_ptr(),
+            MFD_CLOEXEC | MFD_ALLOW_SEALING,
+        )
+    } as c_int)?;
+    Ok(unsafe { FromRawFd::from_raw_fd(fd) })
+}
+
+#[cfg(target_os = "linux")]
+pub fn add_seals(file: &File, seals: c_int) -> io::Result<()> {
+    cvt_err(unsafe { ::libc::fcntl(file.as_raw_fd(), ::libc::F_ADD_SEALS, seals) }).map(|_| ())
+}
+
+#[cfg(target_os = "linux")]
+pub fn get_seals(file: &File) -> io::Result<c_int> {
+    cvt_err(unsafe { ::libc::fcntl(file.as_raw_fd(), ::libc::F_GET_SEALS) })
+}
+
 #[cfg(not(target_os = "linux"))]
 pub fn create(dir: &Path) -> io::Result<File> {
     create_unix(dir)
 }
 
 fn create_unix(dir: &Path) -> io::Result<File> {
-    util::create_helper(dir, ".tmp", "", ::NUM_RAND_CHARS, |path| {
-        create_unlinked(&path)
-    })
+    util::create_helper(dir, &Builder::new(), |path| create_unlinked(&path))
 }
 
 unsafe fn stat(fd: RawFd) -> io::Result<stat_t> {
@@ -104,46 +180,201 @@ unsafe fn stat(fd: RawFd) -> io::Result<stat_t> {
     Ok(meta)
 }
 
-pub fn reopen(file: &File, path: &Path) -> io::Result<File> {
-    let new_file = OpenOptions::new().read(true).write(true).open(path)?;
+fn check_same(file: &File, other: &File) -> io::Result<()> {
     unsafe {
         let old_meta = stat(file.as_raw_fd())?;
-        let new_meta = stat(new_file.as_raw_fd())?;
+        let new_meta = stat(other.as_raw_fd())?;
         if old_meta.st_dev != new_meta.st_dev || old_meta.st_ino != new_meta.st_ino {
             return Err(io::Error::new(
                 io::ErrorKind::NotFound,
                 "original tempfile has been replaced",
             ));
         }
-        Ok(new_file)
     }
+    Ok(())
+}
+
+fn open_checked(file: &File, path: &Path, options: &OpenOptions) -> io::Result<File> {
+    let new_file = options.open(path)?;
+    check_same(file, &new_file)?;
+    Ok(new_file)
+}
+
+// Look the path up with `O_PATH`, which grants neither read nor write access, and only open the
+// file with `options`, through procfs, once it's known to be the original.
+#[cfg(target_os = "linux")]
+fn reopen_with(file: &File, path: &Path, options: &OpenOptions) -> io::Result<File> {
+    use libc::{O_NOFOLLOW, O_PATH};
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let handle = OpenOptions::new()
+        .read(true)
+        .custom_flags(O_PATH | O_NOFOLLOW)
+        .open(path)?;
+    check_same(file, &handle)?;
+    match open_checked(file, &proc_fd_path(&handle), options) {
+        // Without procfs, fall back to the path. The options never create or truncate the
+        // file, so at worst a replaced file is opened and closed again.
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => open_checked(file, path, options),
+        result => result,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reopen_with(file: &File, path: &Path, options: &OpenOptions) -> io::Result<File> {
+    open_checked(file, path, options)
+}
+
+pub fn reopen(file: &File, path: &Path) -> io::Result<File> {
+    reopen_with(file, path, OpenOptions::new().read(true).write(true))
+}
+
+pub fn reopen_read_only(file: &File, path: &Path) -> io::Result<File> {
+    reopen_with(file, path, OpenOptions::new().read(true))
+}
+
+pub fn reopen_append(file: &File, path: &Path) -> io::Result<File> {
+    reopen_with(file, path, OpenOptions::new().append(true))
 }
 
 #[cfg(not(target_os = "redox"))]
-pub fn persist(old_path: &Path, new_path: &Path, overwrite: bool) -> io::Result<()> {
+pub fn persist(old_path: &Path, new_path: &Path, overwrite: bool) -> io::Result<PersistStrategy> {
+    if !overwrite {
+        return rename_noclobber(old_path, new_path);
+    }
     unsafe {
         let old_path = cstr(old_path)?;
         let new_path = cstr(new_path)?;
-        if overwrite {
-            cvt_err(rename(
-                old_path.as_ptr() as *const c_char,
-                new_path.as_ptr() as *const c_char,
-            ))?;
-        } else {
-            cvt_err(link(
-                old_path.as_ptr() as *const c_char,
-                new_path.as_ptr() as *const c_char,
-            ))?;
-            // Ignore unlink errors. Can we do better?
-            // On recent linux, we can use renameat2 to do this atomically.
-            let _ = unlink(old_path.as_ptr() as *const c_char);
+        cvt_err(rename(
+            old_path.as_ptr() as *const c_char,
+            new_path.as_ptr() as *const c_char,
+        ))?;
+        Ok(PersistStrategy::Rename)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn rename_noclobber(old_path: &Path, new_path: &Path) -> io::Result<PersistStrategy> {
+    match sys::rename_noreplace(old_path, new_path) {
+        Ok(()) => Ok(PersistStrategy::RenameNoReplace),
+        Err(ref e) if sys::is_unsupported(e) => link_noclobber(old_path, new_path).map_err(|e| {
+            if e.kind() == io::ErrorKind::AlreadyExists {
+                e
+            } else {
+                io::Error::new(
+                    e.kind(),
+                    format!(
+                        "{} (renameat2 with RENAME_NOREPLACE is unsupported, \
+                         fell back to link and unlink)",
+                        e
+                    ),
+                )
+            }
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "redox")))]
+fn rename_noclobber(old_path: &Path, new_path: &Path) -> io::Result<PersistStrategy> {
+    link_noclobber(old_path, new_path)
+}
+
+#[cfg(not(target_os = "redox"))]
+fn link_noclobber(old_path: &Path, new_path: &Path) -> io::Result<PersistStrategy> {
+    let old_path = cstr(old_path)?;
+    let new_path = cstr(new_path)?;
+    unsafe {
+        cvt_err(link(
+            old_path.as_ptr() as *const c_char,
+            new_path.as_ptr() as *const c_char,
+        ))?;
+        // Ignore unlink errors. Can we do better?
+        let _ = unlink(old_path.as_ptr() as *const c_char);
+    }
+    Ok(PersistStrategy::LinkUnlink)
+}
+
+#[cfg(target_os = "linux")]
+pub fn persist_anonymous(file: &File, new_path: &Path, overwrite: bool) -> io::Result<()> {
+    if !overwrite {
+        return link_anonymous(file, new_path);
+    }
+
+    // `linkat` never replaces an existing file so link the file under a temporary name next to
+    // the target and then rename it into place.
+    let tmp_path = util::create_helper(util::parent_dir(new_path), &Builder::new(), |path| {
+        link_anonymous(file, &path).map(|_| path)
+    })?;
+    persist(&tmp_path, new_path, true).map(|_| ()).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        e
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn link_anonymous(file: &File, new_path: &Path) -> io::Result<()> {
+    use libc::{linkat, AT_EMPTY_PATH, AT_FDCWD, AT_SYMLINK_FOLLOW, ENOENT};
+
+    let new_path = cstr(new_path)?;
+    unsafe {
+        let empty = b"\0";
+        match cvt_err(linkat(
+            file.as_raw_fd(),
+            empty.as_ptr() as *const c_char,
+            AT_FDCWD,
+            new_path.as_ptr() as *const c_char,
+            AT_EMPTY_PATH,
+        )) {
+            // `AT_EMPTY_PATH` requires `CAP_DAC_READ_SEARCH`. Without it, the kernel reports
+            // ENOENT and we have to go through procfs instead.
+            Err(ref e) if e.raw_os_error() == Some(ENOENT) => {
+                let fd_path = cstr(&proc_fd_path(file))?;
+                cvt_err(linkat(
+                    AT_FDCWD,
+                    fd_path.as_ptr() as *const c_char,
+                    AT_FDCWD,
+                    new_path.as_ptr() as *const c_char,
+                    AT_SYMLINK_FOLLOW,
+                ))
+                .map(|_| ())
+            }
+            result => result.map(|_| ()),
         }
-        Ok(())
     }
 }
 
+#[cfg(target_os = "linux")]
+fn proc_fd_path(file: &File) -> PathBuf {
+    PathBuf::from(format!("/proc/self/fd/{}", file.as_raw_fd()))
+}
+
+#[cfg(target_os = "linux")]
+pub fn reopen_anonymous(file: &File) -> io::Result<File> {
+    open_checked(
+        file,
+        &proc_fd_path(file),
+        OpenOptions::new().read(true).write(true),
+    )
+}
+
+#[cfg(target_os = "linux")]
+pub fn anonymous_path(file: &File) -> PathBuf {
+    let pid = unsafe { libc::getpid() };
+    PathBuf::from(format!("/proc/{}/fd/{}", pid, file.as_raw_fd()))
+}
+
+pub fn sync_file(path: &Path) -> io::Result<()> {
+    File::open(path)?.sync_all()
+}
+
+// Directories are opened and synced like files.
+pub fn sync_dir(path: &Path) -> io::Result<()> {
+    sync_file(path)
+}
+
 #[cfg(target_os = "redox")]
-pub fn persist(old_path: &Path, new_path: &Path, overwrite: bool) -> io::Result<()> {
+pub fn persist(old_path: &Path, new_path: &Path, overwrite: bool) -> io::Result<PersistStrategy> {
     // XXX implement when possible
     Err(io::Error::from_raw_os_error(syscall::ENOSYS))
 }
@@ -1,4 +1,4 @@
-use std::fs::File;
+use std::fs::{File, OpenOptions, Permissions};
 use std::io;
 use std::os::windows::ffi::OsStrExt;
 use std::os::windows::io::{AsRawHandle, FromRawHandle, RawHandle};
@@ -10,11 +10,14 @@ use winapi::um::fileapi::{CreateFileW, SetFileAttributesW, CREATE_NEW};
 use winapi::um::handleapi::INVALID_HANDLE_VALUE;
 use winapi::um::winbase::{FILE_FLAG_DELETE_ON_CLOSE, MOVEFILE_REPLACE_EXISTING};
 use winapi::um::winbase::{MoveFileExW, ReOpenFile};
+use winapi::um::winnt::FILE_ATTRIBUTE_READONLY;
 use winapi::um::winnt::{FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_NORMAL, FILE_ATTRIBUTE_TEMPORARY};
-use winapi::um::winnt::{FILE_GENERIC_READ, FILE_GENERIC_WRITE, HANDLE};
+use winapi::um::winnt::{FILE_GENERIC_READ, FILE_GENERIC_WRITE, FILE_WRITE_DATA, HANDLE};
 use winapi::um::winnt::{FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE};
 
+use file::PersistStrategy;
 use util;
+use Builder;
 
 #[cfg_attr(irustfmt, rustfmt_skip)]
 const ACCESS: DWORD     = FILE_GENERIC_READ
@@ -60,12 +63,16 @@ fn win_create(
     }
 }
 
-pub fn create_named(path: &Path) -> io::Result<File> {
-    win_create(path, ACCESS, SHARE_MODE, CREATE_NEW, FLAGS)
+pub fn create_named(path: &Path, permissions: Option<&Permissions>) -> io::Result<File> {
+    let mut flags = FLAGS;
+    if permissions.map_or(false, |p| p.readonly()) {
+        flags |= FILE_ATTRIBUTE_READONLY;
+    }
+    win_create(path, ACCESS, SHARE_MODE, CREATE_NEW, flags)
 }
 
 pub fn create(dir: &Path) -> io::Result<File> {
-    util::create_helper(dir, ".tmp", "", ::NUM_RAND_CHARS, |path| {
+    util::create_helper(dir, &Builder::new(), |path| {
         win_create(
             &path,
             ACCESS,
@@ -77,9 +84,23 @@ pub fn create(dir: &Path) -> io::Result<File> {
 }
 
 pub fn reopen(file: &File, _path: &Path) -> io::Result<File> {
+    reopen_access(file, ACCESS)
+}
+
+pub fn reopen_read_only(file: &File, _path: &Path) -> io::Result<File> {
+    reopen_access(file, FILE_GENERIC_READ)
+}
+
+// Like `OpenOptions::append`: writes without `FILE_WRITE_DATA` always go to the end.
+pub fn reopen_append(file: &File, _path: &Path) -> io::Result<File> {
+    reopen_access(file, FILE_GENERIC_WRITE & !FILE_WRITE_DATA)
+}
+
+// `ReOpenFile` opens the file behind the handle, so it can't have been replaced.
+fn reopen_access(file: &File, access: DWORD) -> io::Result<File> {
     let handle = file.as_raw_handle();
     unsafe {
-        let handle = ReOpenFile(handle as HANDLE, ACCESS, SHARE_MODE, 0);
+        let handle = ReOpenFile(handle as HANDLE, access, SHARE_MODE, 0);
         if handle == INVALID_HANDLE_VALUE {
             Err(io::Error::last_os_error())
         } else {
@@ -88,7 +109,11 @@ pub fn reopen(file: &File, _path: &Path) -> io::Result<File> {
     }
 }
 
-pub fn persist(old_path: &Path, new_path: &Path, overwrite: bool) -> io::Result<()> {
+pub fn persist(
+    old_path: &Path,
+    new_path: &Path,
+    overwrite: bool,
+) -> io::Result<PersistStrategy> {
     // TODO: We should probably do this in one-shot using SetFileInformationByHandle but the API is
     // really painful.
 
@@ -114,8 +139,20 @@ pub fn persist(old_path: &Path, new_path: &Path, overwrite: bool) -> io::Result<
             // (slightly less efficient) but it will still work.
             let _ = SetFileAttributesW(old_path_w.as_ptr(), FLAGS);
             Err(e)
+        } else if overwrite {
+            Ok(PersistStrategy::Rename)
         } else {
-            Ok(())
+            Ok(PersistStrategy::RenameNoReplace)
         }
     }
 }
+
+pub fn sync_file(path: &Path) -> io::Result<()> {
+    // FlushFileBuffers needs write access.
+    OpenOptions::new().write(true).open(path)?.sync_all()
+}
+
+pub fn sync_dir(_path: &Path) -> io::Result<()> {
+    // Directory entries can't be flushed on Windows; NTFS journals metadata changes itself.
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use libc::{self, c_int};
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::{BitOr, BitOrAssign};
+use std::os::unix::io::{AsRawFd, IntoRawFd, RawFd};
+
+use super::imp;
+
+/// Create a new in-memory temporary file with `memfd_create`.
+///
+/// The file lives in memory (or swap) and never touches a filesystem. `name` is only used for
+/// debugging: it shows up in `/proc/self/fd` as `/memfd:name`. Names don't have to be unique.
+///
+/// The file can be sealed with [`MemFile::seal()`], e.g. before passing it to another
+/// process, to guarantee that its contents can no longer change.
+///
+/// This function is only available on Linux 3.17 and later.
+///
+/// # Errors
+///
+/// If the file can not be created, e.g. because the kernel doesn't support `memfd_create` or
+/// `name` contains a null byte or is longer than 249 bytes, `Err` is returned.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// # use std::io;
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// use std::io::Write;
+/// use tempfile::Seals;
+///
+/// let mut blob = tempfile::tempfile_memfd("blob")?;
+/// blob.write_all(b"Brian was here. Briefly.")?;
+///
+/// // From now on, nobody can change the contents.
+/// blob.seal(Seals::WRITE | Seals::SHRINK | Seals::GROW | Seals::SEAL)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`MemFile::seal()`]: struct.MemFile.html#method.seal
+pub fn tempfile_memfd<S: AsRef<OsStr>>(name: S) -> io::Result<MemFile> {
+    imp::create_memfd(name.as_ref()).map(|file| MemFile { file })
+}
+
+/// An in-memory temporary file created with `memfd_create`.
+///
+/// See [`tempfile_memfd()`] and [`Builder::memfd()`]. Like files returned by [`tempfile()`],
+/// the file is freed when the last handle to it is closed.
+///
+/// [`tempfile_memfd()`]: fn.tempfile_memfd.html
+/// [`Builder::memfd()`]: struct.Builder.html#method.memfd
+/// [`tempfile()`]: fn.tempfile.html
+#[derive(Debug)]
+pub struct MemFile {
+    file: File,
+}
+
+impl MemFile {
+    /// Add seals to the file.
+    ///
+    /// Seals restrict which operations are allowed on the file, for everyone holding a handle
+    /// to it, and can't be removed again. Processes receiving the file can check its seals
+    /// with [`MemFile::seals()`] or `fcntl(F_GET_SEALS)` to make sure it won't change under
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// If a seal can't be added, `Err` is returned. Adding [`Seals::WRITE`] fails while the
+    /// file is mapped writably, and adding anything fails once [`Seals::SEAL`] has been set.
+    ///
+    /// [`MemFile::seals()`]: #method.seals
+    /// [`Seals::WRITE`]: struct.Seals.html#associatedconstant.WRITE
+    /// [`Seals::SEAL`]: struct.Seals.html#associatedconstant.SEAL
+    pub fn seal(&self, seals: Seals) -> io::Result<()> {
+        imp::add_seals(&self.file, seals.0)
+    }
+
+    /// Get the seals that have been added to the file.
+    pub fn seals(&self) -> io::Result<Seals> {
+        imp::get_seals(&self.file).map(Seals)
+    }
+
+    /// Get a reference to the underlying file.
+    pub fn as_file(&self) -> &File {
+        &self.file
+    }
+
+    /// Get a mutable reference to the underlying file.
+    pub fn as_file_mut(&mut self) -> &mut File {
+        &mut self.file
+    }
+
+    /// Convert into the underlying file.
+    pub fn into_file(self) -> File {
+        self.file
+    }
+}
+
+impl Read for MemFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl<'a> Read for &'a MemFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.file).read(buf)
+    }
+}
+
+impl Write for MemFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl<'a> Write for &'a MemFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.file).write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.file).flush()
+    }
+}
+
+impl Seek for MemFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+impl<'a> Seek for &'a MemFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        (&self.file).seek(pos)
+    }
+}
+
+impl AsRawFd for MemFile {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for MemFile {
+    #[inline]
+    fn into_raw_fd(self) -> RawFd {
+        self.file.into_raw_fd()
+    }
+}
+
+/// A set of seals for a [`MemFile`].
+///
+/// Combine seals with `|`.
+///
+/// [`MemFile`]: struct.MemFile.html
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Seals(c_int);
+
+impl Seals {
+    /// Prevent adding further seals.
+    pub const SEAL: Seals = Seals(libc::F_SEAL_SEAL);
+    /// Prevent shrinking the file.
+    pub const SHRINK: Seals = Seals(libc::F_SEAL_SHRINK);
+    /// Prevent growing the file, by writing past its end or by extending it.
+    pub const GROW: Seals = Seals(libc::F_SEAL_GROW);
+    /// Prevent modifying the contents of the file.
+    pub const WRITE: Seals = Seals(libc::F_SEAL_WRITE);
+
+    /// No seals.
+    pub fn empty() -> Seals {
+        Seals(0)
+    }
+
+    /// Whether all seals in `other` are also in `self`.
+    pub fn contains(self, other: Seals) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether there are no seals.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for Seals {
+    type Output = Seals;
+
+    fn bitor(self, other: Seals) -> Seals {
+        Seals(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for Seals {
+    fn bitor_assign(&mut self, other: Seals) {
+        self.0 |= other.0;
+    }
+}
+
+impl fmt::Debug for Seals {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = [
+            (Seals::SEAL, "SEAL"),
+            (Seals::SHRINK, "SHRINK"),
+            (Seals::GROW, "GROW"),
+            (Seals::WRITE, "WRITE"),
+        ];
+        let set: Vec<_> = names
+            .iter()
+            .filter(|&&(seal, _)| self.contains(seal))
+            .map(|&(_, name)| name)
+            .collect();
+        write!(f, "Seals({})", set.join(" | "))
+    }
+}
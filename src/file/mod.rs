@@ -1,21 +1,40 @@
 use std;
-use std::env;
 use std::error;
 use std::ffi::OsStr;
 use std::fmt;
 use std::fs::{self, File};
-use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
 use std::mem;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 
+use cleanup;
+use env;
+#[cfg(target_os = "linux")]
+use sys;
+use util;
 use Builder;
 
+#[cfg(unix)]
+mod fifo;
 mod imp;
+#[cfg(target_os = "linux")]
+mod memfd;
+mod shared;
+#[cfg(unix)]
+mod socket;
+
+#[cfg(unix)]
+pub use self::fifo::{create_fifo, NamedTempFifo};
+#[cfg(target_os = "linux")]
+pub use self::memfd::{tempfile_memfd, MemFile, Seals};
+pub use self::shared::SharedTempFile;
+#[cfg(unix)]
+pub use self::socket::{bind_unix_listener, unix_listener, TempUnixListener};
 
 /// Create a new temporary file.
 ///
-/// The file will be created in the location returned by [`std::env::temp_dir()`].
+/// The file will be created in the location returned by [`env::temp_dir()`].
 ///
 /// # Security
 ///
@@ -51,7 +70,7 @@ mod imp;
 /// # }
 /// ```
 ///
-/// [`std::env::temp_dir()`]: https://doc.rust-lang.org/std/env/fn.temp_dir.html
+/// [`env::temp_dir()`]: env/fn.temp_dir.html
 pub fn tempfile() -> io::Result<File> {
     tempfile_in(&env::temp_dir())
 }
@@ -98,6 +117,316 @@ pub fn tempfile_in<P: AsRef<Path>>(dir: P) -> io::Result<File> {
     imp::create(dir.as_ref())
 }
 
+/// Give an anonymous temporary file a name.
+///
+/// `file` must have been created with [`Builder::linkable_tempfile_in()`]. Files returned by
+/// [`tempfile()`] can never be named. This is useful for files that should only ever become
+/// visible once they are complete: until this function is called, nothing appears in the
+/// filesystem and, if the program crashes, nothing is left behind.
+///
+/// If a file exists at the target path, it is atomically replaced. The file stays open and
+/// can still be used after it has been named.
+///
+/// Note: Temporary files cannot be persisted across filesystems.
+///
+/// # Errors
+///
+/// If the file cannot be linked at the new location, `Err` is returned.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate tempfile;
+/// use tempfile::Builder;
+/// use std::io::{self, Write};
+///
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// let mut file = Builder::new().linkable_tempfile_in("./")?;
+/// writeln!(file, "Brian was here. Briefly.")?;
+///
+/// tempfile::persist_anonymous(&file, "./saved_file.txt")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Builder::linkable_tempfile_in()`]: struct.Builder.html#method.linkable_tempfile_in
+/// [`tempfile()`]: fn.tempfile.html
+#[cfg(target_os = "linux")]
+pub fn persist_anonymous<P: AsRef<Path>>(file: &File, new_path: P) -> io::Result<()> {
+    imp::persist_anonymous(file, new_path.as_ref(), true)
+}
+
+/// Give an anonymous temporary file a name iff no file exists there.
+///
+/// See [`persist_anonymous()`] for details. Unlike that function, this one fails if a file
+/// already exists at the target path.
+///
+/// # Errors
+///
+/// If the file cannot be linked at the new location or a file already exists there, `Err` is
+/// returned.
+///
+/// [`persist_anonymous()`]: fn.persist_anonymous.html
+#[cfg(target_os = "linux")]
+pub fn persist_anonymous_noclobber<P: AsRef<Path>>(file: &File, new_path: P) -> io::Result<()> {
+    imp::persist_anonymous(file, new_path.as_ref(), false)
+}
+
+/// Reopen an anonymous temporary file.
+///
+/// Unlike [`File::try_clone()`], which shares the cursor with the original, this opens a new
+/// handle to the same file through `/proc/self/fd`, with its own cursor. This works for
+/// files returned by [`tempfile()`] even though they have no name, e.g. to read the data
+/// back independently while it's still being written.
+///
+/// Like [`NamedTempFile::reopen()`], this makes sure the new handle refers to the same file.
+///
+/// # Errors
+///
+/// If the file cannot be reopened, e.g. because `/proc` isn't mounted, `Err` is returned.
+///
+/// # Examples
+///
+/// ```no_run
+/// # extern crate tempfile;
+/// use std::io::{self, Read, Write};
+///
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// let mut writer = tempfile::tempfile()?;
+/// let mut reader = tempfile::reopen_anonymous(&writer)?;
+///
+/// writeln!(writer, "Brian was here. Briefly.")?;
+/// let mut buf = String::new();
+/// reader.read_to_string(&mut buf)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`File::try_clone()`]: https://doc.rust-lang.org/std/fs/struct.File.html#method.try_clone
+/// [`tempfile()`]: fn.tempfile.html
+/// [`NamedTempFile::reopen()`]: struct.NamedTempFile.html#method.reopen
+#[cfg(target_os = "linux")]
+pub fn reopen_anonymous(file: &File) -> io::Result<File> {
+    imp::reopen_anonymous(file)
+}
+
+/// Get a path under which an anonymous temporary file can be opened.
+///
+/// The path has the form `/proc/<pid>/fd/<fd>`, so it can be passed to other processes, e.g.
+/// to a child process as a command line argument, and opened there without inheriting the
+/// file descriptor. It stays valid only as long as `file` is open in this process, and
+/// opening it requires the same permissions as inspecting this process.
+///
+/// Unlike [`reopen_anonymous()`], opening the path doesn't check that it still refers to
+/// the same file.
+///
+/// [`reopen_anonymous()`]: fn.reopen_anonymous.html
+#[cfg(target_os = "linux")]
+pub fn anonymous_path(file: &File) -> PathBuf {
+    imp::anonymous_path(file)
+}
+
+/// Create a new spooled temporary file.
+///
+/// See [`SpooledTempFile`] for details.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// use tempfile::spooled_tempfile;
+/// use std::io::{self, Write};
+///
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// // Keep up to 4KiB in memory before rolling over to a file.
+/// let mut file = spooled_tempfile(4096);
+///
+/// writeln!(file, "Brian was here. Briefly.")?;
+/// assert!(!file.is_rolled());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`SpooledTempFile`]: struct.SpooledTempFile.html
+pub fn spooled_tempfile(max_size: usize) -> SpooledTempFile {
+    SpooledTempFile::new(max_size)
+}
+
+/// The contents of a [`SpooledTempFile`].
+///
+/// [`SpooledTempFile`]: struct.SpooledTempFile.html
+#[derive(Debug)]
+pub enum SpooledData {
+    /// The data is still held in memory.
+    InMemory(Cursor<Vec<u8>>),
+    /// The data has been rolled over to an anonymous temporary file.
+    OnDisk(File),
+}
+
+/// A temporary file that is kept in memory until it grows past a size threshold.
+///
+/// Writes are buffered in memory until they would grow the file beyond `max_size` bytes,
+/// at which point the data is moved into an anonymous temporary file created with
+/// [`tempfile()`]. The file can also be rolled over explicitly with
+/// [`SpooledTempFile::roll_over()`]. Reads, writes and seeks behave the same way in both
+/// states.
+///
+/// # Resource Leaking
+///
+/// Once rolled over, the file behaves like one returned by [`tempfile()`]: it will be removed
+/// by the OS when it is closed.
+///
+/// [`tempfile()`]: fn.tempfile.html
+/// [`SpooledTempFile::roll_over()`]: #method.roll_over
+#[derive(Debug)]
+pub struct SpooledTempFile {
+    max_size: usize,
+    inner: SpooledData,
+}
+
+impl SpooledTempFile {
+    /// Create a new spooled temporary file that rolls over once it grows past `max_size`
+    /// bytes.
+    pub fn new(max_size: usize) -> SpooledTempFile {
+        SpooledTempFile {
+            max_size,
+            inner: SpooledData::InMemory(Cursor::new(Vec::new())),
+        }
+    }
+
+    /// Returns `true` if the data has been moved into a file.
+    #[inline]
+    pub fn is_rolled(&self) -> bool {
+        match self.inner {
+            SpooledData::InMemory(_) => false,
+            SpooledData::OnDisk(_) => true,
+        }
+    }
+
+    /// Move the data into an anonymous temporary file, preserving the current position.
+    ///
+    /// Does nothing if the data is already in a file.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be created or written to, `Err` is returned and the data stays
+    /// in memory.
+    pub fn roll_over(&mut self) -> io::Result<()> {
+        let file = match self.inner {
+            SpooledData::InMemory(ref cursor) => {
+                let mut file = tempfile()?;
+                file.write_all(cursor.get_ref())?;
+                file.seek(SeekFrom::Start(cursor.position()))?;
+                file
+            }
+            SpooledData::OnDisk(_) => return Ok(()),
+        };
+        self.inner = SpooledData::OnDisk(file);
+        Ok(())
+    }
+
+    /// Truncate or extend the file to `size` bytes, rolling over if `size` is past the
+    /// threshold.
+    pub fn set_len(&mut self, size: u64) -> io::Result<()> {
+        if size > self.max_size as u64 {
+            self.roll_over()?;
+        }
+        match self.inner {
+            SpooledData::InMemory(ref mut cursor) => {
+                cursor.get_mut().resize(size as usize, 0);
+                Ok(())
+            }
+            SpooledData::OnDisk(ref file) => file.set_len(size),
+        }
+    }
+
+    /// Consume the `SpooledTempFile`, returning either the in-memory data or the file.
+    pub fn into_inner(self) -> SpooledData {
+        self.inner
+    }
+}
+
+impl Read for SpooledTempFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.inner {
+            SpooledData::InMemory(ref mut cursor) => cursor.read(buf),
+            SpooledData::OnDisk(ref mut file) => file.read(buf),
+        }
+    }
+}
+
+impl Write for SpooledTempFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Roll over before the write would take us past the threshold.
+        let overflow = match self.inner {
+            SpooledData::InMemory(ref cursor) => {
+                cursor.position().saturating_add(buf.len() as u64) > self.max_size as u64
+            }
+            SpooledData::OnDisk(_) => false,
+        };
+        if overflow {
+            self.roll_over()?;
+        }
+
+        match self.inner {
+            SpooledData::InMemory(ref mut cursor) => cursor.write(buf),
+            SpooledData::OnDisk(ref mut file) => file.write(buf),
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner {
+            SpooledData::InMemory(ref mut cursor) => cursor.flush(),
+            SpooledData::OnDisk(ref mut file) => file.flush(),
+        }
+    }
+}
+
+impl Seek for SpooledTempFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self.inner {
+            SpooledData::InMemory(ref mut cursor) => cursor.seek(pos),
+            SpooledData::OnDisk(ref mut file) => file.seek(pos),
+        }
+    }
+}
+
+/// How a temporary file was moved to its new path.
+///
+/// Returned by [`NamedTempFile::persist_noclobber_with_strategy()`] and
+/// [`TempPath::persist_noclobber_with_strategy()`].
+///
+/// [`NamedTempFile::persist_noclobber_with_strategy()`]: struct.NamedTempFile.html#method.persist_noclobber_with_strategy
+/// [`TempPath::persist_noclobber_with_strategy()`]: struct.TempPath.html#method.persist_noclobber_with_strategy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PersistStrategy {
+    /// A plain rename, replacing any file at the new path.
+    Rename,
+    /// An atomic rename that fails if a file exists at the new path: `renameat2` with
+    /// `RENAME_NOREPLACE` on Linux, `MoveFileExW` on Windows.
+    RenameNoReplace,
+    /// A hard link at the new path, followed by removing the old path. Used where the atomic
+    /// rename isn't supported. If removing the old path fails, the file is left with both
+    /// names.
+    LinkUnlink,
+}
+
 /// Error returned when persisting a temporary file path fails.
 #[derive(Debug)]
 pub struct PathPersistError {
@@ -142,9 +471,12 @@ impl error::Error for PathPersistError {
 /// This is useful when the temporary file needs to be used by a child process,
 /// for example.
 ///
-/// When dropped, the temporary file is deleted.
+/// When dropped, the temporary file is deleted unless it's being [kept].
+///
+/// [kept]: #method.keep
 pub struct TempPath {
     path: PathBuf,
+    keep: bool,
 }
 
 impl TempPath {
@@ -183,12 +515,35 @@ impl TempPath {
     /// # }
     /// ```
     pub fn close(mut self) -> io::Result<()> {
-        let result = fs::remove_file(&self.path);
+        cleanup::unregister(&self.path);
+        let result = if util::should_keep(self.keep, &self.path) {
+            Ok(())
+        } else {
+            fs::remove_file(&self.path)
+        };
         mem::replace(&mut self.path, PathBuf::new());
         mem::forget(self);
         result
     }
 
+    /// Keep the temporary file instead of deleting it when the `TempPath` is dropped or closed.
+    ///
+    /// This is mostly useful for debugging: the file can be inspected after the program is
+    /// done with it. Setting the `TEMPFILE_KEEP` environment variable to a value other than
+    /// `0` keeps every temporary file and directory and reports their paths on stderr.
+    ///
+    /// See also [`Builder::keep()`].
+    ///
+    /// [`Builder::keep()`]: struct.Builder.html#method.keep
+    pub fn keep(&mut self, keep: bool) {
+        self.keep = keep;
+        if keep {
+            cleanup::unregister(&self.path);
+        } else {
+            cleanup::register_file(&self.path);
+        }
+    }
+
     /// Persist the temporary file at the target path.
     ///
     /// If a file exists at the target path, persist will atomically replace it.
@@ -230,21 +585,8 @@ impl TempPath {
     /// ```
     ///
     /// [`PathPersistError`]: struct.PathPersistError.html
-    pub fn persist<P: AsRef<Path>>(mut self, new_path: P) -> Result<(), PathPersistError> {
-        match imp::persist(&self.path, new_path.as_ref(), true) {
-            Ok(_) => {
-                // Don't drop `self`. We don't want to try deleting the old
-                // temporary file path. (It'll fail, but the failure is never
-                // seen.)
-                mem::replace(&mut self.path, PathBuf::new());
-                mem::forget(self);
-                Ok(())
-            }
-            Err(e) => Err(PathPersistError {
-                error: e,
-                path: self,
-            }),
-        }
+    pub fn persist<P: AsRef<Path>>(self, new_path: P) -> Result<(), PathPersistError> {
+        self.persist_inner(new_path.as_ref(), true, false).map(|_| ())
     }
 
     /// Persist the temporary file at the target path iff no file exists there.
@@ -253,8 +595,11 @@ impl TempPath {
     /// return `self` in the resulting [`PathPersistError`].
     ///
     /// Note: Temporary files cannot be persisted across filesystems. Also Note:
-    /// This method is not atomic. It can leave the original link to the
-    /// temporary file behind.
+    /// On Linux, this method is atomic if the kernel and filesystem support
+    /// `renameat2` with `RENAME_NOREPLACE`. Otherwise, it falls back on creating
+    /// a hard link and then removing the temporary one, which isn't atomic, can
+    /// leave the original link to the temporary file behind, and fails on
+    /// filesystems that don't support hard links.
     ///
     /// # Security
     ///
@@ -290,18 +635,91 @@ impl TempPath {
     /// ```
     ///
     /// [`PathPersistError`]: struct.PathPersistError.html
-    pub fn persist_noclobber<P: AsRef<Path>>(
-        mut self,
+    pub fn persist_noclobber<P: AsRef<Path>>(self, new_path: P) -> Result<(), PathPersistError> {
+        self.persist_inner(new_path.as_ref(), false, false).map(|_| ())
+    }
+
+    /// Durably persist the temporary file at the target path.
+    ///
+    /// Like [`TempPath::persist()`] but also flushes the file's data to disk before moving
+    /// it and the affected directories afterwards. Once this method returns `Ok`, the file
+    /// will be found at the target path even after a power loss.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be synced or moved to the new location, `Err` is returned. If
+    /// syncing a directory fails, the file has already been moved: the [`PathPersistError`]
+    /// then holds an empty path so that dropping it doesn't delete anything.
+    ///
+    /// [`TempPath::persist()`]: #method.persist
+    /// [`PathPersistError`]: struct.PathPersistError.html
+    pub fn persist_durable<P: AsRef<Path>>(self, new_path: P) -> Result<(), PathPersistError> {
+        if let Err(error) = imp::sync_file(&self.path) {
+            return Err(PathPersistError { error, path: self });
+        }
+        self.persist_inner(new_path.as_ref(), true, true).map(|_| ())
+    }
+
+    /// Durably persist the temporary file at the target path iff no file exists there.
+    ///
+    /// Like [`TempPath::persist_noclobber()`] but syncs the file and directories to disk
+    /// like [`TempPath::persist_durable()`] does.
+    ///
+    /// # Errors
+    ///
+    /// See [`TempPath::persist_noclobber()`] and [`TempPath::persist_durable()`].
+    ///
+    /// [`TempPath::persist_noclobber()`]: #method.persist_noclobber
+    /// [`TempPath::persist_durable()`]: #method.persist_durable
+    pub fn persist_noclobber_durable<P: AsRef<Path>>(
+        self,
         new_path: P,
     ) -> Result<(), PathPersistError> {
-        match imp::persist(&self.path, new_path.as_ref(), false) {
-            Ok(_) => {
+        if let Err(error) = imp::sync_file(&self.path) {
+            return Err(PathPersistError { error, path: self });
+        }
+        self.persist_inner(new_path.as_ref(), false, true).map(|_| ())
+    }
+
+    /// Persist the temporary file at the target path iff no file exists there, and report how.
+    ///
+    /// Like [`TempPath::persist_noclobber()`], but on success returns the
+    /// [`PersistStrategy`] that was used, e.g. to log when the fallback on creating a hard
+    /// link was needed.
+    ///
+    /// # Errors
+    ///
+    /// See [`TempPath::persist_noclobber()`].
+    ///
+    /// [`TempPath::persist_noclobber()`]: #method.persist_noclobber
+    /// [`PersistStrategy`]: enum.PersistStrategy.html
+    pub fn persist_noclobber_with_strategy<P: AsRef<Path>>(
+        self,
+        new_path: P,
+    ) -> Result<PersistStrategy, PathPersistError> {
+        self.persist_inner(new_path.as_ref(), false, false)
+    }
+
+    fn persist_inner(
+        mut self,
+        new_path: &Path,
+        overwrite: bool,
+        durable: bool,
+    ) -> Result<PersistStrategy, PathPersistError> {
+        match imp::persist(&self.path, new_path, overwrite) {
+            Ok(strategy) => {
                 // Don't drop `self`. We don't want to try deleting the old
                 // temporary file path. (It'll fail, but the failure is never
                 // seen.)
-                mem::replace(&mut self.path, PathBuf::new());
+                let old_path = mem::replace(&mut self.path, PathBuf::new());
+                cleanup::unregister(&old_path);
+                if durable {
+                    if let Err(error) = sync_parent_dirs(&old_path, new_path) {
+                        return Err(PathPersistError { error, path: self });
+                    }
+                }
                 mem::forget(self);
-                Ok(())
+                Ok(strategy)
             }
             Err(e) => Err(PathPersistError {
                 error: e,
@@ -311,6 +729,18 @@ impl TempPath {
     }
 }
 
+// Sync the directory the file was moved into and, if it's a different one, the directory it
+// was removed from.
+fn sync_parent_dirs(old_path: &Path, new_path: &Path) -> io::Result<()> {
+    let new_dir = util::parent_dir(new_path);
+    imp::sync_dir(new_dir)?;
+    let old_dir = util::parent_dir(old_path);
+    if old_dir != new_dir {
+        imp::sync_dir(old_dir)?;
+    }
+    Ok(())
+}
+
 impl fmt::Debug for TempPath {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.path.fmt(f)
@@ -319,7 +749,10 @@ impl fmt::Debug for TempPath {
 
 impl Drop for TempPath {
     fn drop(&mut self) {
-        let _ = fs::remove_file(&self.path);
+        cleanup::unregister(&self.path);
+        if !util::should_keep(self.keep, &self.path) {
+            let _ = fs::remove_file(&self.path);
+        }
     }
 }
 
@@ -346,7 +779,7 @@ impl AsRef<OsStr> for TempPath {
 /// A named temporary file.
 ///
 /// The default constructor, [`NamedTempFile::new()`], creates files in
-/// the location returned by [`std::env::temp_dir()`], but `NamedTempFile`
+/// the location returned by [`env::temp_dir()`], but `NamedTempFile`
 /// can be configured to manage a temporary file in any location
 /// by constructing with [`NamedTempFile::new_in()`].
 ///
@@ -359,14 +792,16 @@ impl AsRef<OsStr> for TempPath {
 /// If the program exits before the `NamedTempFile` destructor is
 /// run, such as via [`std::process::exit()`], by segfaulting, or by
 /// receiving a signal like `SIGINT`, then the temporary file
-/// will not be deleted.
+/// will not be deleted. On Unix, [`cleanup_on_exit()`] deletes it in
+/// the first and last case.
 ///
 /// Use the [`tempfile()`] function unless you absolutely need a named file.
 ///
 /// [`tempfile()`]: fn.tempfile.html
+/// [`cleanup_on_exit()`]: fn.cleanup_on_exit.html
 /// [`NamedTempFile::new()`]: #method.new
 /// [`NamedTempFile::new_in()`]: #method.new_in
-/// [`std::env::temp_dir()`]: https://doc.rust-lang.org/std/env/fn.temp_dir.html
+/// [`env::temp_dir()`]: env/fn.temp_dir.html
 /// [`std::process::exit()`]: http://doc.rust-lang.org/std/process/fn.exit.html
 pub struct NamedTempFile {
     path: TempPath,
@@ -528,6 +963,16 @@ impl NamedTempFile {
         &self.path
     }
 
+    /// Keep the temporary file instead of deleting it when the `NamedTempFile` is dropped or
+    /// closed.
+    ///
+    /// See [`TempPath::keep()`].
+    ///
+    /// [`TempPath::keep()`]: struct.TempPath.html#method.keep
+    pub fn keep(&mut self, keep: bool) {
+        self.path.keep(keep);
+    }
+
     /// Close and remove the temporary file.
     ///
     /// Use this if you want to detect errors in deleting the file.
@@ -606,17 +1051,7 @@ impl NamedTempFile {
     ///
     /// [`PersistError`]: struct.PersistError.html
     pub fn persist<P: AsRef<Path>>(self, new_path: P) -> Result<File, PersistError> {
-        let NamedTempFile { path, file } = self;
-        match path.persist(new_path) {
-            Ok(_) => Ok(file),
-            Err(err) => {
-                let PathPersistError { error, path } = err;
-                Err(PersistError {
-                    file: NamedTempFile { path, file },
-                    error,
-                })
-            }
-        }
+        self.persist_inner(new_path.as_ref(), true, false).map(|(file, _)| file)
     }
 
     /// Persist the temporary file at the target path iff no file exists there.
@@ -625,8 +1060,11 @@ impl NamedTempFile {
     /// return `self` in the resulting PersistError.
     ///
     /// Note: Temporary files cannot be persisted across filesystems. Also Note:
-    /// This method is not atomic. It can leave the original link to the
-    /// temporary file behind.
+    /// On Linux, this method is atomic if the kernel and filesystem support
+    /// `renameat2` with `RENAME_NOREPLACE`. Otherwise, it falls back on creating
+    /// a hard link and then removing the temporary one, which isn't atomic, can
+    /// leave the original link to the temporary file behind, and fails on
+    /// filesystems that don't support hard links.
     ///
     /// # Security
     ///
@@ -660,9 +1098,190 @@ impl NamedTempFile {
     /// # }
     /// ```
     pub fn persist_noclobber<P: AsRef<Path>>(self, new_path: P) -> Result<File, PersistError> {
+        self.persist_inner(new_path.as_ref(), false, false).map(|(file, _)| file)
+    }
+
+    /// Durably persist the temporary file at the target path.
+    ///
+    /// Like [`NamedTempFile::persist()`] but also flushes the file's data to disk before
+    /// moving it and the affected directories afterwards. Once this method returns `Ok`, the
+    /// file will be found at the target path even after a power loss.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be synced or moved to the new location, `Err` is returned. If
+    /// syncing a directory fails, the file has already been moved: the [`PersistError`] then
+    /// holds a `NamedTempFile` with an empty path so that dropping it doesn't delete anything.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::io::{self, Write};
+    /// # extern crate tempfile;
+    /// use tempfile::NamedTempFile;
+    ///
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// let mut file = NamedTempFile::new_in("./")?;
+    /// writeln!(file, "Brian was here. Briefly.")?;
+    ///
+    /// file.persist_durable("./saved_file.txt")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`NamedTempFile::persist()`]: #method.persist
+    /// [`PersistError`]: struct.PersistError.html
+    pub fn persist_durable<P: AsRef<Path>>(self, new_path: P) -> Result<File, PersistError> {
+        self.persist_inner(new_path.as_ref(), true, true).map(|(file, _)| file)
+    }
+
+    /// Durably persist the temporary file at the target path iff no file exists there.
+    ///
+    /// Like [`NamedTempFile::persist_noclobber()`] but syncs the file and directories to disk
+    /// like [`NamedTempFile::persist_durable()`] does.
+    ///
+    /// # Errors
+    ///
+    /// See [`NamedTempFile::persist_noclobber()`] and [`NamedTempFile::persist_durable()`].
+    ///
+    /// [`NamedTempFile::persist_noclobber()`]: #method.persist_noclobber
+    /// [`NamedTempFile::persist_durable()`]: #method.persist_durable
+    pub fn persist_noclobber_durable<P: AsRef<Path>>(
+        self,
+        new_path: P,
+    ) -> Result<File, PersistError> {
+        self.persist_inner(new_path.as_ref(), false, true).map(|(file, _)| file)
+    }
+
+    /// Atomically swap the temporary file with the existing file at `target`.
+    ///
+    /// Afterwards, the temporary file is at `target`, and the file that was there is at the
+    /// temporary path. Returns the file now at `target` and a [`TempPath`] for the previous
+    /// file, which is deleted when dropped unless it's kept, and can be inspected or swapped
+    /// back to roll back. If this fails, the temporary file is returned in the
+    /// [`PersistError`].
+    ///
+    /// This uses `renameat2` with `RENAME_EXCHANGE` and is only available on Linux. Only the
+    /// exchange itself is atomic: `target` is checked not to be a directory beforehand, but if
+    /// another process replaces it in between, whatever is at `target` then is swapped.
+    ///
+    /// # Errors
+    ///
+    /// If `target` is a directory or doesn't exist, or the kernel or filesystem doesn't
+    /// support exchanging paths, or the files can't be swapped for another reason, `Err` is
+    /// returned and nothing is changed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::io::{self, Write};
+    /// # extern crate tempfile;
+    /// use tempfile::NamedTempFile;
+    ///
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// let mut file = NamedTempFile::new_in(".")?;
+    /// writeln!(file, "answer = 42")?;
+    ///
+    /// let (_config, previous) = file.persist_exchange("./config.toml")?;
+    /// // Roll back.
+    /// previous.persist("./config.toml")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`TempPath`]: struct.TempPath.html
+    /// [`PersistError`]: struct.PersistError.html
+    #[cfg(target_os = "linux")]
+    pub fn persist_exchange<P: AsRef<Path>>(
+        self,
+        target: P,
+    ) -> Result<(File, TempPath), PersistError> {
+        let target = target.as_ref();
+        // Best effort: the kernel happily exchanges a file and a directory.
+        let result = fs::symlink_metadata(target).and_then(|metadata| {
+            if metadata.is_dir() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "target is a directory",
+                ));
+            }
+            sys::rename_exchange(&self.path, target)
+        });
+        match result {
+            Ok(()) => {
+                let NamedTempFile { path, file } = self;
+                Ok((file, path))
+            }
+            Err(error) => Err(PersistError { error, file: self }),
+        }
+    }
+
+    /// Persist the temporary file at the target path iff no file exists there, and report how.
+    ///
+    /// Like [`NamedTempFile::persist_noclobber()`], but on success also returns the
+    /// [`PersistStrategy`] that was used.
+    ///
+    /// # Errors
+    ///
+    /// See [`NamedTempFile::persist_noclobber()`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::io;
+    /// # extern crate tempfile;
+    /// use tempfile::{NamedTempFile, PersistStrategy};
+    ///
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// let file = NamedTempFile::new()?;
+    ///
+    /// let (_file, strategy) = file.persist_noclobber_with_strategy("./saved_file.txt")?;
+    /// if strategy == PersistStrategy::LinkUnlink {
+    ///     println!("renameat2 is unavailable, persisted with link and unlink");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`NamedTempFile::persist_noclobber()`]: #method.persist_noclobber
+    /// [`PersistStrategy`]: enum.PersistStrategy.html
+    pub fn persist_noclobber_with_strategy<P: AsRef<Path>>(
+        self,
+        new_path: P,
+    ) -> Result<(File, PersistStrategy), PersistError> {
+        self.persist_inner(new_path.as_ref(), false, false)
+    }
+
+    fn persist_inner(
+        self,
+        new_path: &Path,
+        overwrite: bool,
+        durable: bool,
+    ) -> Result<(File, PersistStrategy), PersistError> {
+        if durable {
+            if let Err(error) = self.file.sync_all() {
+                return Err(PersistError { error, file: self });
+            }
+        }
+
         let NamedTempFile { path, file } = self;
-        match path.persist_noclobber(new_path) {
-            Ok(_) => Ok(file),
+        match path.persist_inner(new_path, overwrite, durable) {
+            Ok(strategy) => Ok((file, strategy)),
             Err(err) => {
                 let PathPersistError { error, path } = err;
                 Err(PersistError {
@@ -707,6 +1326,55 @@ impl NamedTempFile {
         imp::reopen(self.as_file(), NamedTempFile::path(self))
     }
 
+    /// Reopen the temporary file for reading only.
+    ///
+    /// Like [`NamedTempFile::reopen()`], but the new handle can't be used to write to the file,
+    /// e.g. to hand it to a consumer while this one stays writable. This also works after the
+    /// file has been made read-only.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be reopened, `Err` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::io;
+    /// # extern crate tempfile;
+    /// use tempfile::NamedTempFile;
+    ///
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// let file = NamedTempFile::new()?;
+    ///
+    /// let reader = file.reopen_read_only()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`NamedTempFile::reopen()`]: #method.reopen
+    pub fn reopen_read_only(&self) -> io::Result<File> {
+        imp::reopen_read_only(self.as_file(), NamedTempFile::path(self))
+    }
+
+    /// Reopen the temporary file for appending.
+    ///
+    /// Like [`NamedTempFile::reopen()`], but all writes through the new handle go to the end
+    /// of the file, and it can't be used to read, e.g. to hand it to a logger.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be reopened, `Err` is returned.
+    ///
+    /// [`NamedTempFile::reopen()`]: #method.reopen
+    pub fn reopen_append(&self) -> io::Result<File> {
+        imp::reopen_append(self.as_file(), NamedTempFile::path(self))
+    }
+
     /// Get a reference to the underlying file.
     pub fn as_file(&self) -> &File {
         &self.file
@@ -794,9 +1462,33 @@ impl std::os::windows::io::AsRawHandle for NamedTempFile {
 }
 
 // pub(crate)
-pub fn create_named(path: PathBuf) -> io::Result<NamedTempFile> {
-    imp::create_named(&path).map(|file| NamedTempFile {
-        path: TempPath { path },
+#[cfg(target_os = "linux")]
+pub fn create_linkable(dir: &Path, permissions: Option<&fs::Permissions>) -> io::Result<File> {
+    imp::create_linkable(dir, permissions)
+}
+
+// pub(crate)
+pub fn create_named(
+    path: PathBuf,
+    permissions: Option<&fs::Permissions>,
+    keep: bool,
+) -> io::Result<NamedTempFile> {
+    imp::create_named(&path, permissions).map(|file| from_parts(file, path, keep))
+}
+
+// pub(crate)
+#[cfg(feature = "tokio")]
+pub fn into_parts(file: NamedTempFile) -> (File, TempPath) {
+    (file.file, file.path)
+}
+
+// pub(crate)
+pub fn from_parts(file: File, path: PathBuf, keep: bool) -> NamedTempFile {
+    if !keep {
+        cleanup::register_file(&path);
+    }
+    NamedTempFile {
+        path: TempPath { path, keep },
         file,
-    })
+    }
 }
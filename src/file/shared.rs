@@ -0,0 +1,228 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+#[cfg(unix)]
+use std::os::unix::fs::FileExt;
+#[cfg(windows)]
+use std::os::windows::fs::FileExt;
+
+use super::NamedTempFile;
+
+/// A named temporary file that can be shared between threads and accessed by offset.
+///
+/// Clones are cheap and refer to the same file, which is deleted when the last clone is
+/// dropped. Instead of going through a shared cursor, reads and writes take the offset they
+/// apply to, so many threads can write to different parts of the file at the same time
+/// without a lock, e.g. when downloading chunks of a file in parallel.
+///
+/// Create one from a [`NamedTempFile`], e.g. one configured with a [`Builder`], or with
+/// [`SharedTempFile::new()`].
+///
+/// # Platform notes
+///
+/// On Unix, this uses `pread` and `pwrite`. On Windows, the positional reads and writes also
+/// move the underlying file's cursor, which is not otherwise used by `SharedTempFile`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// # use std::io;
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// use std::thread;
+/// use tempfile::SharedTempFile;
+///
+/// let file = SharedTempFile::new()?;
+///
+/// let writers: Vec<_> = (0..4u8)
+///     .map(|i| {
+///         let file = file.clone();
+///         thread::spawn(move || file.write_all_at(&[b'a' + i; 4], u64::from(i) * 4))
+///     })
+///     .collect();
+/// for writer in writers {
+///     writer.join().unwrap()?;
+/// }
+///
+/// let mut buf = [0; 16];
+/// file.read_exact_at(&mut buf, 0)?;
+/// assert_eq!(&buf, b"aaaabbbbccccdddd");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`NamedTempFile`]: struct.NamedTempFile.html
+/// [`Builder`]: struct.Builder.html
+/// [`SharedTempFile::new()`]: #method.new
+#[derive(Clone)]
+pub struct SharedTempFile {
+    inner: Arc<NamedTempFile>,
+}
+
+impl fmt::Debug for SharedTempFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SharedTempFile({:?})", self.path())
+    }
+}
+
+impl AsRef<Path> for SharedTempFile {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl From<NamedTempFile> for SharedTempFile {
+    fn from(file: NamedTempFile) -> SharedTempFile {
+        SharedTempFile {
+            inner: Arc::new(file),
+        }
+    }
+}
+
+impl SharedTempFile {
+    /// Create a new shared temporary file.
+    ///
+    /// See [`NamedTempFile::new()`] for details.
+    ///
+    /// # Errors
+    ///
+    /// If the file can not be created, `Err` is returned.
+    ///
+    /// [`NamedTempFile::new()`]: struct.NamedTempFile.html#method.new
+    pub fn new() -> io::Result<SharedTempFile> {
+        NamedTempFile::new().map(SharedTempFile::from)
+    }
+
+    /// Create a new shared temporary file in the specified directory.
+    ///
+    /// # Errors
+    ///
+    /// If the file can not be created, `Err` is returned.
+    pub fn new_in<P: AsRef<Path>>(dir: P) -> io::Result<SharedTempFile> {
+        NamedTempFile::new_in(dir).map(SharedTempFile::from)
+    }
+
+    /// Get the temporary file's path.
+    pub fn path(&self) -> &Path {
+        self.inner.path()
+    }
+
+    /// Get a reference to the underlying file.
+    pub fn as_file(&self) -> &File {
+        self.inner.as_file()
+    }
+
+    /// Read bytes starting at `offset`, returning how many were read.
+    ///
+    /// # Errors
+    ///
+    /// If the read fails, `Err` is returned.
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        read_at(self.as_file(), buf, offset)
+    }
+
+    /// Read exactly enough bytes to fill `buf`, starting at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// If the read fails or the file ends before `buf` is filled, `Err` is returned. In the
+    /// latter case, its kind is `UnexpectedEof`.
+    pub fn read_exact_at(&self, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.read_at(buf, offset) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "failed to fill whole buffer",
+                    ))
+                }
+                Ok(n) => {
+                    let tmp = buf;
+                    buf = &mut tmp[n..];
+                    offset += n as u64;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Write bytes starting at `offset`, returning how many were written.
+    ///
+    /// Writing past the end of the file extends it, filling any gap with zeros.
+    ///
+    /// # Errors
+    ///
+    /// If the write fails, `Err` is returned.
+    pub fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        write_at(self.as_file(), buf, offset)
+    }
+
+    /// Write all of `buf` starting at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// If the write fails, `Err` is returned.
+    pub fn write_all_at(&self, mut buf: &[u8], mut offset: u64) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.write_at(buf, offset) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write whole buffer",
+                    ))
+                }
+                Ok(n) => {
+                    buf = &buf[n..];
+                    offset += n as u64;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert back into a [`NamedTempFile`] if this is the only clone left.
+    ///
+    /// This is useful to persist the file once all threads are done with it.
+    ///
+    /// # Errors
+    ///
+    /// If other clones still exist, `self` is returned as the error.
+    ///
+    /// [`NamedTempFile`]: struct.NamedTempFile.html
+    pub fn try_into_inner(self) -> Result<NamedTempFile, SharedTempFile> {
+        Arc::try_unwrap(self.inner).map_err(|inner| SharedTempFile { inner })
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    file.read_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    file.seek_read(buf, offset)
+}
+
+#[cfg(unix)]
+fn write_at(file: &File, buf: &[u8], offset: u64) -> io::Result<usize> {
+    file.write_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn write_at(file: &File, buf: &[u8], offset: u64) -> io::Result<usize> {
+    file.seek_write(buf, offset)
+}
@@ -0,0 +1,217 @@
+use libc::sockaddr_un;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::mem;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+use super::TempPath;
+use cleanup;
+use env;
+use Builder;
+
+/// A Unix domain socket listener bound to a temporary path that is deleted when dropped.
+///
+/// See [`Builder::unix_listener()`] for how the path is chosen.
+///
+/// This is only available on Unix.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// # use std::io;
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// use std::io::{Read, Write};
+/// use tempfile::Builder;
+///
+/// let server = Builder::new().prefix("server").unix_listener()?;
+///
+/// let mut client = server.connect()?;
+/// client.write_all(b"ping")?;
+///
+/// let (mut connection, _) = server.as_listener().accept()?;
+/// let mut buf = [0; 4];
+/// connection.read_exact(&mut buf)?;
+/// assert_eq!(&buf, b"ping");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Builder::unix_listener()`]: struct.Builder.html#method.unix_listener
+pub struct TempUnixListener {
+    listener: UnixListener,
+    path: TempPath,
+}
+
+impl fmt::Debug for TempUnixListener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TempUnixListener({:?})", self.path)
+    }
+}
+
+impl AsRef<Path> for TempUnixListener {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl TempUnixListener {
+    /// Get the path the socket is bound to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get a reference to the listener.
+    pub fn as_listener(&self) -> &UnixListener {
+        &self.listener
+    }
+
+    /// Connect to the socket.
+    ///
+    /// # Errors
+    ///
+    /// If the connection fails, `Err` is returned.
+    pub fn connect(&self) -> io::Result<UnixStream> {
+        UnixStream::connect(self.path())
+    }
+
+    /// Keep the socket file instead of deleting it when the listener is dropped or closed.
+    ///
+    /// See [`TempPath::keep()`](struct.TempPath.html#method.keep).
+    pub fn keep(&mut self, keep: bool) {
+        self.path.keep(keep);
+    }
+
+    /// Close the listener and remove the socket file.
+    ///
+    /// Use this if you want to detect errors in deleting the socket file.
+    ///
+    /// # Errors
+    ///
+    /// If the socket file cannot be deleted, `Err` is returned.
+    pub fn close(self) -> io::Result<()> {
+        let TempUnixListener { listener, path } = self;
+        drop(listener);
+        path.close()
+    }
+
+    /// Split into the listener and the [`TempPath`] of the socket file.
+    ///
+    /// [`TempPath`]: struct.TempPath.html
+    pub fn into_parts(self) -> (UnixListener, TempPath) {
+        (self.listener, self.path)
+    }
+}
+
+// The longest path that fits into `sockaddr_un`, leaving room for the terminating null.
+fn max_socket_path_len() -> usize {
+    let addr: sockaddr_un = unsafe { mem::zeroed() };
+    addr.sun_path.len() - 1
+}
+
+// pub(crate)
+// Bind a listener in `socket_dir`. The length of the names is only estimated there: custom
+// name generators and `Builder::adaptive_rand_bytes` may produce longer ones, so if they turn
+// out not to fit, try again in `/tmp`.
+pub fn unix_listener(builder: &Builder) -> io::Result<TempUnixListener> {
+    let dir = socket_dir(builder);
+    match builder.unix_listener_in(&dir) {
+        Err(ref e)
+            if is_path_too_long(e)
+                && dir != Path::new(FALLBACK_DIR)
+                && Path::new(FALLBACK_DIR).is_dir() =>
+        {
+            builder.unix_listener_in(FALLBACK_DIR)
+        }
+        res => res,
+    }
+}
+
+// The default directory for sockets: `env::temp_dir()` if the names generated by `builder`
+// fit into `sun_path` there, otherwise `/tmp`, which is short.
+fn socket_dir(builder: &Builder) -> PathBuf {
+    let name_len = builder.prefix.len() + builder.random_len + builder.suffix.len();
+    let fits = |dir: &Path| dir.as_os_str().len() + 1 + name_len <= max_socket_path_len();
+    let temp_dir = env::temp_dir();
+    if !fits(&temp_dir) && fits(Path::new(FALLBACK_DIR)) && Path::new(FALLBACK_DIR).is_dir() {
+        return PathBuf::from(FALLBACK_DIR);
+    }
+    temp_dir
+}
+
+const FALLBACK_DIR: &str = "/tmp";
+
+// pub(crate)
+pub fn bind_unix_listener(
+    path: PathBuf,
+    permissions: Option<&fs::Permissions>,
+    keep: bool,
+) -> io::Result<TempUnixListener> {
+    if path.as_os_str().len() > max_socket_path_len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            PathTooLong { path },
+        ));
+    }
+    let listener = UnixListener::bind(&path).map_err(|e| {
+        // Let `util::create_helper` try another name.
+        if e.kind() == io::ErrorKind::AddrInUse {
+            io::Error::new(io::ErrorKind::AlreadyExists, e)
+        } else {
+            e
+        }
+    })?;
+    if !keep {
+        cleanup::register_file(&path);
+    }
+    let listener = TempUnixListener {
+        listener,
+        path: TempPath { path, keep },
+    };
+    // `bind` has no mode argument, so the permissions can only be applied afterwards. If this
+    // fails, dropping `listener` removes the socket file again.
+    if let Some(permissions) = permissions {
+        fs::set_permissions(listener.path(), permissions.clone())?;
+    }
+    Ok(listener)
+}
+
+// The error returned when a socket path doesn't fit into `sun_path`.
+#[derive(Debug)]
+struct PathTooLong {
+    path: PathBuf,
+}
+
+impl fmt::Display for PathTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "socket path {} is {} bytes long, but at most {} bytes fit into sun_path",
+            self.path.display(),
+            self.path.as_os_str().len(),
+            max_socket_path_len()
+        )
+    }
+}
+
+impl error::Error for PathTooLong {
+    fn description(&self) -> &str {
+        "socket path too long"
+    }
+}
+
+fn is_path_too_long(err: &io::Error) -> bool {
+    match err.get_ref() {
+        Some(e) => e.is::<PathTooLong>(),
+        None => false,
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! - Use the [`tempfile()`] function for temporary files
 //! - Use the [`tempdir()`] function for temporary directories.
+//! - Use the [`spooled_tempfile()`] function for temporary data that is usually small enough
+//!   to keep in memory.
 //!
 //! # Design
 //!
@@ -12,6 +14,9 @@
 //! When choosing between the temporary file variants, prefer `tempfile`
 //! unless you either need to know the file's path or to be able to persist it.
 //!
+//! Unless told otherwise, temporary files and directories are created in [`env::temp_dir()`],
+//! which can be redirected for the whole process without changing `TMPDIR`.
+//!
 //! ## Resource Leaking
 //!
 //! `tempfile` will (almost) never fail to cleanup temporary resources but `TempDir` and `NamedTempFile` will if
@@ -83,9 +88,11 @@
 //!
 //! [`tempfile()`]: fn.tempfile.html
 //! [`tempdir()`]: fn.tempdir.html
+//! [`spooled_tempfile()`]: fn.spooled_tempfile.html
 //! [`TempDir`]: struct.TempDir.html
 //! [`NamedTempFile`]: struct.NamedTempFile.html
 //! [`std::env::temp_dir()`]: https://doc.rust-lang.org/std/env/fn.temp_dir.html
+//! [`env::temp_dir()`]: env/fn.temp_dir.html
 
 #![doc(html_logo_url = "https://www.rust-lang.org/logos/rust-logo-128x128-blk-v2.png",
        html_favicon_url = "https://www.rust-lang.org/favicon.ico",
@@ -93,6 +100,7 @@
 #![cfg_attr(test, deny(warnings))]
 
 extern crate rand;
+#[cfg(not(unix))]
 extern crate remove_dir_all;
 
 #[cfg(unix)]
@@ -104,33 +112,122 @@ extern crate winapi;
 #[cfg(target_os = "redox")]
 extern crate syscall;
 
+#[cfg(feature = "tokio")]
+extern crate tokio as tokio_rt;
+
 const NUM_RETRIES: u32 = 1 << 31;
 const NUM_RAND_CHARS: usize = 6;
 
 use std::path::Path;
-use std::{env, io};
+use std::sync::{Arc, Mutex};
+use std::{fmt, fs, io};
 
+mod atomic;
+mod cleanup;
 mod dir;
+pub mod env;
 mod file;
+mod name;
+mod reap;
+#[cfg(any(unix, target_os = "redox"))]
+mod sys;
+#[cfg(feature = "tokio")]
+pub mod tokio;
 mod util;
 
-pub use dir::{tempdir, tempdir_in, TempDir};
-pub use file::{tempfile, tempfile_in, NamedTempFile, PersistError, TempPath};
+pub use atomic::{write_atomic, AtomicWriteFile};
+#[cfg(unix)]
+pub use cleanup::cleanup_on_exit;
+pub use dir::{tempdir, tempdir_in, DirPersistError, TempDir};
+#[cfg(unix)]
+pub use file::{NamedTempFifo, TempUnixListener};
+#[cfg(target_os = "linux")]
+pub use file::{
+    anonymous_path, persist_anonymous, persist_anonymous_noclobber, reopen_anonymous,
+    tempfile_memfd, MemFile, Seals,
+};
+pub use file::{
+    spooled_tempfile, tempfile, tempfile_in, NamedTempFile, PersistError, PersistStrategy,
+    SharedTempFile, SpooledData, SpooledTempFile, TempPath,
+};
+pub use name::{
+    set_entropy_source, Alphanumeric, Entropy, Hex, Lowercase, NameGenerator, ProcessId, Timestamp,
+};
+pub use reap::{reap, ReapOptions};
+pub use util::CollisionError;
 
 /// Create a new temporary file or directory with custom parameters.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Clone)]
 pub struct Builder<'a, 'b> {
     random_len: usize,
     prefix: &'a str,
     suffix: &'b str,
+    permissions: Option<fs::Permissions>,
+    keep: bool,
+    dir_handle: bool,
+    names: Option<Arc<NameGenerator + Send + Sync>>,
+    retries: u32,
+    adaptive_rand_bytes: bool,
+    rng: Option<Arc<Mutex<Entropy + Send>>>,
+}
+
+impl<'a, 'b> fmt::Debug for Builder<'a, 'b> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Builder")
+            .field("random_len", &self.random_len)
+            .field("prefix", &self.prefix)
+            .field("suffix", &self.suffix)
+            .field("permissions", &self.permissions)
+            .field("keep", &self.keep)
+            .field("dir_handle", &self.dir_handle)
+            .field("names", &self.names.as_ref().map(|_| "NameGenerator"))
+            .field("retries", &self.retries)
+            .field("adaptive_rand_bytes", &self.adaptive_rand_bytes)
+            .field("rng", &self.rng.as_ref().map(|_| "Entropy"))
+            .finish()
+    }
+}
+
+fn same_arc<T: ?Sized>(a: Option<&Arc<T>>, b: Option<&Arc<T>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+// Name generators and random sources can't be compared, so builders are only equal if they share
+// the same ones.
+impl<'a, 'b> PartialEq for Builder<'a, 'b> {
+    fn eq(&self, other: &Self) -> bool {
+        self.random_len == other.random_len
+            && self.prefix == other.prefix
+            && self.suffix == other.suffix
+            && self.permissions == other.permissions
+            && self.keep == other.keep
+            && self.dir_handle == other.dir_handle
+            && same_arc(self.names.as_ref(), other.names.as_ref())
+            && self.retries == other.retries
+            && self.adaptive_rand_bytes == other.adaptive_rand_bytes
+            && same_arc(self.rng.as_ref(), other.rng.as_ref())
+    }
 }
 
+impl<'a, 'b> Eq for Builder<'a, 'b> {}
+
 impl<'a, 'b> Default for Builder<'a, 'b> {
     fn default() -> Self {
         Builder {
             random_len: ::NUM_RAND_CHARS,
             prefix: ".tmp",
             suffix: "",
+            permissions: None,
+            keep: false,
+            dir_handle: false,
+            names: None,
+            retries: ::NUM_RETRIES,
+            adaptive_rand_bytes: false,
+            rng: None,
         }
     }
 }
@@ -294,6 +391,327 @@ impl<'a, 'b> Builder<'a, 'b> {
         self
     }
 
+    /// Set the scheme used to generate the random part of the name.
+    ///
+    /// The generator is called with the number of random bytes set with
+    /// [`Builder::rand_bytes()`] and its result is placed between the prefix and the suffix. If
+    /// a file or directory with the resulting name already exists, the generator is called
+    /// again.
+    ///
+    /// Besides the default [`Alphanumeric`], the crate provides [`Lowercase`], [`Hex`],
+    /// [`Timestamp`] and [`ProcessId`]. Closures taking the length and returning a `String`
+    /// can be used as well.
+    ///
+    /// Default: [`Alphanumeric`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// use tempfile::{Builder, Entropy, Hex, NameGenerator, Timestamp};
+    ///
+    /// // Names that sort by creation time.
+    /// let named_tempfile = Builder::new()
+    ///     .name_with(Timestamp)
+    ///     .tempfile()?;
+    ///
+    /// // Uppercase hexadecimal names.
+    /// struct UpperHex;
+    ///
+    /// impl NameGenerator for UpperHex {
+    ///     fn generate(&self, rng: &mut Entropy, len: usize) -> String {
+    ///         Hex.generate(rng, len).to_uppercase()
+    ///     }
+    /// }
+    ///
+    /// let named_tempfile = Builder::new()
+    ///     .name_with(UpperHex)
+    ///     .tempfile()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Builder::rand_bytes()`]: #method.rand_bytes
+    /// [`Alphanumeric`]: struct.Alphanumeric.html
+    /// [`Lowercase`]: struct.Lowercase.html
+    /// [`Hex`]: struct.Hex.html
+    /// [`Timestamp`]: struct.Timestamp.html
+    /// [`ProcessId`]: struct.ProcessId.html
+    pub fn name_with<G>(&mut self, names: G) -> &mut Self
+    where
+        G: NameGenerator + Send + Sync + 'static,
+    {
+        self.names = Some(Arc::new(names));
+        self
+    }
+
+    /// Set how many names are tried before giving up.
+    ///
+    /// Whenever a file or directory with the generated name already exists, a new name is
+    /// generated. After `retries` names have been tried, creating the file or directory fails
+    /// with an error of kind `AlreadyExists` wrapping a [`CollisionError`]. At least one name is
+    /// always tried.
+    ///
+    /// Default: `2^31`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// let named_tempfile = Builder::new()
+    ///     .rand_bytes(2)
+    ///     .retries(100)
+    ///     .tempfile()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`CollisionError`]: struct.CollisionError.html
+    pub fn retries(&mut self, retries: u32) -> &mut Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Lengthen the random part of the name after repeated collisions.
+    ///
+    /// With this enabled, every four consecutive names that are already taken add another
+    /// character to the random part of the name, starting from [`Builder::rand_bytes()`]. This
+    /// keeps short names in the common case while guaranteeing progress in crowded directories.
+    ///
+    /// Default: `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// let named_tempfile = Builder::new()
+    ///     .rand_bytes(1)
+    ///     .adaptive_rand_bytes(true)
+    ///     .tempfile()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Builder::rand_bytes()`]: #method.rand_bytes
+    pub fn adaptive_rand_bytes(&mut self, adaptive: bool) -> &mut Self {
+        self.adaptive_rand_bytes = adaptive;
+        self
+    }
+
+    /// Set the source of random bytes used to generate names.
+    ///
+    /// By default, the process-wide source installed with [`set_entropy_source()`] is used.
+    /// Clones of the builder share the source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// let mut counter = 0u8;
+    /// let named_tempfile = Builder::new()
+    ///     .rng(move |dest: &mut [u8]| {
+    ///         for byte in dest.iter_mut() {
+    ///             *byte = counter;
+    ///             counter = counter.wrapping_add(1);
+    ///         }
+    ///     })
+    ///     .tempfile()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`set_entropy_source()`]: fn.set_entropy_source.html
+    pub fn rng<R>(&mut self, rng: R) -> &mut Self
+    where
+        R: Entropy + Send + 'static,
+    {
+        self.rng = Some(Arc::new(Mutex::new(rng)));
+        self
+    }
+
+    /// Generate names from a deterministic source of random bytes seeded with `seed`.
+    ///
+    /// Builders with the same seed and settings generate the same sequence of names, which is
+    /// useful for tests whose output contains temporary paths. Generators that embed the time
+    /// or the process ID, like [`Timestamp`] and [`ProcessId`], still vary between runs.
+    ///
+    /// The generated names are predictable, so don't use this in directories shared with other
+    /// users.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// let first = Builder::new().seed(42).tempdir()?;
+    /// let first_path = first.path().to_owned();
+    /// first.close()?;
+    ///
+    /// let second = Builder::new().seed(42).tempdir()?;
+    /// assert_eq!(first_path, second.path());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Timestamp`]: struct.Timestamp.html
+    /// [`ProcessId`]: struct.ProcessId.html
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.rng(name::Seeded::new(seed))
+    }
+
+    /// Set the permissions of the created file or directory.
+    ///
+    /// The permissions are applied when the file or directory is created, so it never exists
+    /// with more permissions than requested. On Unix they are applied exactly, ignoring the
+    /// process umask. On Windows only the read-only flag of files is honored.
+    ///
+    /// Default: `0o600` for files, `0o777` (minus the umask) for directories.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # #[cfg(unix)]
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// use std::fs::Permissions;
+    /// use std::os::unix::fs::PermissionsExt;
+    ///
+    /// let shared_tempfile = Builder::new()
+    ///     .permissions(Permissions::from_mode(0o640))
+    ///     .tempfile()?;
+    ///
+    /// let private_tempdir = Builder::new()
+    ///     .permissions(Permissions::from_mode(0o700))
+    ///     .tempdir()?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(unix))]
+    /// # fn run() -> Result<(), io::Error> {
+    /// #     Ok(())
+    /// # }
+    /// ```
+    pub fn permissions(&mut self, permissions: fs::Permissions) -> &mut Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    /// Keep the created file or directory instead of deleting it when it's dropped or closed.
+    ///
+    /// This is mostly useful for debugging. Setting the `TEMPFILE_KEEP` environment variable
+    /// to a value other than `0` has the same effect on every temporary file and directory
+    /// in the process and additionally reports their paths on stderr.
+    ///
+    /// Default: `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// let tmp_dir = Builder::new()
+    ///     .keep(true)
+    ///     .tempdir()?;
+    /// # ::std::fs::remove_dir(tmp_dir.path())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn keep(&mut self, keep: bool) -> &mut Self {
+        self.keep = keep;
+        self
+    }
+
+    /// Keep a handle to the created directory open for the lifetime of the [`TempDir`].
+    ///
+    /// This enables [`TempDir::create_file()`] and friends, as well as
+    /// [`Builder::tempfile_at()`], which operate relative to the handle with the `openat` family
+    /// of functions instead of going through the directory's path. Unlike path-based access,
+    /// they can't be redirected by replacing one of the directory's parents with a symbolic
+    /// link, which makes them suitable for staging areas in world-writable directories like
+    /// `/tmp`.
+    ///
+    /// Default: `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// let tmp_dir = Builder::new()
+    ///     .dir_handle(true)
+    ///     .tempdir()?;
+    /// tmp_dir.create_dir("staging")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`TempDir`]: struct.TempDir.html
+    /// [`TempDir::create_file()`]: struct.TempDir.html#method.create_file
+    /// [`Builder::tempfile_at()`]: #method.tempfile_at
+    #[cfg(unix)]
+    pub fn dir_handle(&mut self, dir_handle: bool) -> &mut Self {
+        self.dir_handle = dir_handle;
+        self
+    }
+
     /// Create the named temporary file.
     ///
     /// # Security
@@ -365,16 +783,257 @@ impl<'a, 'b> Builder<'a, 'b> {
     /// [security]: struct.NamedTempFile.html#security
     /// [resource-leaking]: struct.NamedTempFile.html#resource-leaking
     pub fn tempfile_in<P: AsRef<Path>>(&self, dir: P) -> io::Result<NamedTempFile> {
-        util::create_helper(
-            dir.as_ref(),
-            self.prefix,
-            self.suffix,
-            self.random_len,
-            file::create_named,
-        )
+        util::create_helper(dir.as_ref(), self, |path| {
+            file::create_named(path, self.permissions.as_ref(), self.keep)
+        })
+    }
+
+    /// Create the named temporary file in the specified temporary directory, relative to its
+    /// directory handle.
+    ///
+    /// The file is created with `openat`, so the directory's path isn't resolved again and
+    /// can't be redirected by a symbolic link. The returned `NamedTempFile` still refers to the
+    /// file by its full path when it's persisted or deleted.
+    ///
+    /// # Errors
+    ///
+    /// If `dir` was created without [`Builder::dir_handle()`] or the file cannot be created,
+    /// `Err` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// let tmp_dir = Builder::new().dir_handle(true).tempdir()?;
+    /// let tempfile = Builder::new().suffix(".json").tempfile_at(&tmp_dir)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Builder::dir_handle()`]: #method.dir_handle
+    #[cfg(unix)]
+    pub fn tempfile_at(&self, dir: &TempDir) -> io::Result<NamedTempFile> {
+        util::create_helper_named(dir.path(), self, |name, path| {
+            let file = dir::create_named_at(dir, name, self.permissions.as_ref())?;
+            Ok(file::from_parts(file, path, self.keep))
+        })
+    }
+
+    /// Create an anonymous temporary file that can be given a name later.
+    ///
+    /// See [`Builder::linkable_tempfile_in()`] for details.
+    ///
+    /// [`Builder::linkable_tempfile_in()`]: #method.linkable_tempfile_in
+    #[cfg(target_os = "linux")]
+    pub fn linkable_tempfile(&self) -> io::Result<fs::File> {
+        self.linkable_tempfile_in(&env::temp_dir())
+    }
+
+    /// Create an anonymous temporary file in the specified directory that can be given a name
+    /// later.
+    ///
+    /// Like [`tempfile()`], the file has no name and is removed by the OS once it's closed.
+    /// Unlike it, the file can be linked into the filesystem with [`persist_anonymous()`].
+    /// As files can't be persisted across filesystems, create the file in the directory it
+    /// will eventually be persisted to.
+    ///
+    /// The prefix, suffix and number of random bytes are ignored. [`Builder::permissions()`] is
+    /// honored.
+    ///
+    /// # Errors
+    ///
+    /// If the file can not be created, `Err` is returned. This includes filesystems that
+    /// don't support `O_TMPFILE`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # extern crate tempfile;
+    /// # use std::io::{self, Write};
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// let mut file = Builder::new().linkable_tempfile_in("./downloads")?;
+    /// writeln!(file, "Brian was here. Briefly.")?;
+    ///
+    /// // Nothing appears in `./downloads` until now.
+    /// tempfile::persist_anonymous_noclobber(&file, "./downloads/note.txt")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`tempfile()`]: fn.tempfile.html
+    /// [`persist_anonymous()`]: fn.persist_anonymous.html
+    /// [`Builder::permissions()`]: #method.permissions
+    #[cfg(target_os = "linux")]
+    pub fn linkable_tempfile_in<P: AsRef<Path>>(&self, dir: P) -> io::Result<fs::File> {
+        file::create_linkable(dir.as_ref(), self.permissions.as_ref())
+    }
+
+    /// Create a named pipe (FIFO) in [`env::temp_dir()`].
+    ///
+    /// The FIFO is named like a temporary file, with the prefix, random characters and the
+    /// suffix, and deleted when the returned [`NamedTempFifo`] is dropped, unless it's kept.
+    ///
+    /// # Errors
+    ///
+    /// If the FIFO cannot be created, `Err` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// let fifo = Builder::new().prefix("input").fifo()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`env::temp_dir()`]: env/fn.temp_dir.html
+    /// [`NamedTempFifo`]: struct.NamedTempFifo.html
+    #[cfg(unix)]
+    pub fn fifo(&self) -> io::Result<NamedTempFifo> {
+        self.fifo_in(&env::temp_dir())
+    }
+
+    /// Create a named pipe (FIFO) in the specified directory.
+    ///
+    /// See [`Builder::fifo()`].
+    ///
+    /// # Errors
+    ///
+    /// If the FIFO cannot be created, `Err` is returned.
+    ///
+    /// [`Builder::fifo()`]: #method.fifo
+    #[cfg(unix)]
+    pub fn fifo_in<P: AsRef<Path>>(&self, dir: P) -> io::Result<NamedTempFifo> {
+        util::create_helper(dir.as_ref(), self, |path| {
+            file::create_fifo(path, self.permissions.as_ref(), self.keep)
+        })
+    }
+
+    /// Bind a Unix domain socket listener to a new path in a temporary directory.
+    ///
+    /// The socket file is named like a temporary file, with the prefix, random characters and
+    /// the suffix, and deleted when the returned [`TempUnixListener`] is dropped, unless it's
+    /// kept.
+    ///
+    /// Socket paths must fit into the `sun_path` field of `sockaddr_un`, which holds only 104
+    /// to 108 bytes depending on the platform. If the names wouldn't fit into
+    /// [`env::temp_dir()`], e.g. because `TMPDIR` is deeply nested, `/tmp` is used instead.
+    /// This is also the case if a name generated with a custom [`NameGenerator`] or
+    /// [`Builder::adaptive_rand_bytes()`] turns out to be too long for [`env::temp_dir()`]. Use
+    /// [`Builder::unix_listener_in()`] to choose the directory yourself.
+    ///
+    /// The permissions set with [`Builder::permissions()`] are applied right after binding the
+    /// socket, which is created with the default permissions minus the umask.
+    ///
+    /// # Errors
+    ///
+    /// If the path is too long even for `/tmp`, or the socket cannot be bound, `Err` is
+    /// returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// let server = Builder::new().prefix("server").unix_listener()?;
+    /// // Hand `server.path()` to the clients.
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`TempUnixListener`]: struct.TempUnixListener.html
+    /// [`env::temp_dir()`]: env/fn.temp_dir.html
+    /// [`NameGenerator`]: trait.NameGenerator.html
+    /// [`Builder::adaptive_rand_bytes()`]: #method.adaptive_rand_bytes
+    /// [`Builder::unix_listener_in()`]: #method.unix_listener_in
+    /// [`Builder::permissions()`]: #method.permissions
+    #[cfg(unix)]
+    pub fn unix_listener(&self) -> io::Result<TempUnixListener> {
+        file::unix_listener(self)
+    }
+
+    /// Bind a Unix domain socket listener to a new path in the specified directory.
+    ///
+    /// See [`Builder::unix_listener()`].
+    ///
+    /// # Errors
+    ///
+    /// If the path doesn't fit into `sun_path`, or the socket cannot be bound, `Err` is
+    /// returned.
+    ///
+    /// [`Builder::unix_listener()`]: #method.unix_listener
+    #[cfg(unix)]
+    pub fn unix_listener_in<P: AsRef<Path>>(&self, dir: P) -> io::Result<TempUnixListener> {
+        util::create_helper(dir.as_ref(), self, |path| {
+            file::bind_unix_listener(path, self.permissions.as_ref(), self.keep)
+        })
+    }
+
+    /// Create an in-memory temporary file with `memfd_create`.
+    ///
+    /// The file never touches a filesystem. Its name, made of the prefix, random characters
+    /// and the suffix, is only visible in `/proc/self/fd` for debugging. The other options
+    /// don't apply.
+    ///
+    /// See [`tempfile_memfd()`] for details.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be created, `Err` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// # use tempfile::Builder;
+    /// let file = Builder::new().prefix("blob").memfd()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`tempfile_memfd()`]: fn.tempfile_memfd.html
+    #[cfg(target_os = "linux")]
+    pub fn memfd(&self) -> io::Result<MemFile> {
+        tempfile_memfd(util::tmpname(self, self.random_len))
     }
 
-    /// Attempts to make a temporary directory inside of `env::temp_dir()` whose
+    /// Attempts to make a temporary directory inside of [`env::temp_dir()`] whose
     /// name will have the prefix, `prefix`. The directory and
     /// everything inside it will be automatically deleted once the
     /// returned `TempDir` is destroyed.
@@ -402,6 +1061,7 @@ impl<'a, 'b> Builder<'a, 'b> {
     /// ```
     ///
     /// [resource-leaking]: struct.TempDir.html#resource-leaking
+    /// [`env::temp_dir()`]: env/fn.temp_dir.html
     pub fn tempdir(&self) -> io::Result<TempDir> {
         self.tempdir_in(&env::temp_dir())
     }
@@ -437,11 +1097,77 @@ impl<'a, 'b> Builder<'a, 'b> {
         let storage;
         let mut dir = dir.as_ref();
         if !dir.is_absolute() {
-            let cur_dir = env::current_dir()?;
+            let cur_dir = ::std::env::current_dir()?;
             storage = cur_dir.join(dir);
             dir = &storage;
         }
 
-        util::create_helper(dir, self.prefix, self.suffix, self.random_len, dir::create)
+        util::create_helper(dir, self, |path| {
+            dir::create(path, self.permissions.as_ref(), self.keep, self.dir_handle)
+        })
+    }
+
+    /// Check whether the process that created the temporary file or directory at `path` has
+    /// exited.
+    ///
+    /// This only works for names generated with this builder's prefix and suffix by
+    /// [`ProcessId`], which records the creator's process ID and hostname in the name. The
+    /// path itself isn't accessed.
+    ///
+    /// Returns `false` if the name doesn't match, if the creator ran on a different host or if
+    /// its liveness can't be determined on this platform, so that a `true` result means the
+    /// file or directory can be deleted without disturbing its creator. Only Unix is supported
+    /// so far. On Linux, the name also records when the creator started, so a process that
+    /// reuses its ID isn't mistaken for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate tempfile;
+    /// # use std::io;
+    /// # fn main() {
+    /// #     if let Err(_) = run() {
+    /// #         ::std::process::exit(1);
+    /// #     }
+    /// # }
+    /// # fn run() -> Result<(), io::Error> {
+    /// use std::fs;
+    /// use tempfile::{Builder, ProcessId};
+    ///
+    /// let scratch = tempfile::tempdir()?;
+    /// let mut builder = Builder::new();
+    /// builder.prefix("worker-").name_with(ProcessId);
+    ///
+    /// let work_dir = builder.tempdir_in(scratch.path())?;
+    ///
+    /// // Clean up after workers that crashed, leaving the others alone.
+    /// for entry in fs::read_dir(scratch.path())? {
+    ///     let path = entry?.path();
+    ///     if builder.is_orphaned(&path) {
+    ///         fs::remove_dir_all(&path)?;
+    ///     }
+    /// }
+    /// assert!(work_dir.path().exists());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ProcessId`]: struct.ProcessId.html
+    pub fn is_orphaned<P: AsRef<Path>>(&self, path: P) -> bool {
+        let name = match path.as_ref().file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => return false,
+        };
+        match util::random_part(name, self.prefix, self.suffix).and_then(name::Owner::parse) {
+            Some(owner) => owner.is_dead(),
+            None => false,
+        }
+    }
+
+    fn names(&self) -> &NameGenerator {
+        match self.names {
+            Some(ref names) => &**names,
+            None => &Alphanumeric,
+        }
     }
 }
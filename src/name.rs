@@ -0,0 +1,393 @@
+use rand::{self, Rng, SeedableRng, XorShiftRng};
+#[cfg(target_os = "linux")]
+use std::fs::File;
+#[cfg(unix)]
+use std::io;
+#[cfg(target_os = "linux")]
+use std::io::Read;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of random bytes for generating names.
+///
+/// By default, names are generated from the process-wide source, which can be replaced with
+/// [`set_entropy_source()`]. [`Builder::rng()`] and [`Builder::seed()`] set the source for a
+/// single builder.
+///
+/// Closures taking a byte slice to fill implement `Entropy`.
+///
+/// [`set_entropy_source()`]: fn.set_entropy_source.html
+/// [`Builder::rng()`]: struct.Builder.html#method.rng
+/// [`Builder::seed()`]: struct.Builder.html#method.seed
+pub trait Entropy {
+    /// Fill `dest` with random bytes.
+    fn fill_bytes(&mut self, dest: &mut [u8]);
+}
+
+impl<F> Entropy for F
+where
+    F: FnMut(&mut [u8]),
+{
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self(dest)
+    }
+}
+
+// The installed source, or null for `thread_rng`. Replaced sources are leaked, since another
+// thread may still be using them.
+static ENTROPY_SOURCE: AtomicPtr<fn(&mut [u8])> = AtomicPtr::new(ptr::null_mut());
+
+/// Replace the process-wide source of random bytes used for names.
+///
+/// By default, names are generated from `rand::thread_rng()`. Install a different source in
+/// environments where that's undesirable, e.g. to avoid sharing random state across `fork` or
+/// in sandboxes where the OS random number generator isn't available. Builders with their own
+/// source set with [`Builder::rng()`] or [`Builder::seed()`] aren't affected.
+///
+/// # Examples
+///
+/// ```
+/// fn counter(dest: &mut [u8]) {
+///     use std::sync::atomic::{AtomicUsize, Ordering};
+///     static COUNTER: AtomicUsize = AtomicUsize::new(0);
+///     for byte in dest.iter_mut() {
+///         *byte = COUNTER.fetch_add(1, Ordering::Relaxed) as u8;
+///     }
+/// }
+///
+/// tempfile::set_entropy_source(counter);
+/// ```
+///
+/// [`Builder::rng()`]: struct.Builder.html#method.rng
+/// [`Builder::seed()`]: struct.Builder.html#method.seed
+pub fn set_entropy_source(source: fn(&mut [u8])) {
+    ENTROPY_SOURCE.store(Box::into_raw(Box::new(source)), Ordering::SeqCst);
+}
+
+// pub(crate)
+// The process-wide source of random bytes.
+pub struct GlobalEntropy;
+
+impl Entropy for GlobalEntropy {
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let source = ENTROPY_SOURCE.load(Ordering::SeqCst);
+        if source.is_null() {
+            rand::thread_rng().fill_bytes(dest)
+        } else {
+            // Safe because installed sources are never freed.
+            unsafe { (*source)(dest) }
+        }
+    }
+}
+
+// pub(crate)
+// A deterministic source of random bytes, see `Builder::seed`.
+pub struct Seeded(XorShiftRng);
+
+impl Seeded {
+    pub fn new(seed: u64) -> Seeded {
+        // The xorshift state must not be all zeros.
+        let (lo, hi) = (seed as u32, (seed >> 32) as u32);
+        Seeded(XorShiftRng::from_seed([
+            lo,
+            hi,
+            lo ^ 0x9e37_79b9,
+            hi ^ 0x7f4a_7c15,
+        ]))
+    }
+}
+
+impl Entropy for Seeded {
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+}
+
+// pub(crate)
+// Run `f` with the builder's source of random bytes or, if there is none, the global one.
+pub fn with_entropy<F, R>(rng: Option<&Mutex<Entropy + Send>>, f: F) -> R
+where
+    F: FnOnce(&mut Entropy) -> R,
+{
+    match rng {
+        Some(rng) => {
+            // A panic while generating a name doesn't leave the source in an unusable state.
+            let mut rng = match rng.lock() {
+                Ok(rng) => rng,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            f(&mut *rng)
+        }
+        None => f(&mut GlobalEntropy),
+    }
+}
+
+/// A scheme for generating the names of temporary files and directories.
+///
+/// A `NameGenerator` produces the part of the name between the [`Builder`]'s prefix and suffix.
+/// If the generated name is already taken, a new one is requested, so the generated names
+/// should contain enough randomness to avoid colliding repeatedly. Draw the randomness from the
+/// provided [`Entropy`] so that [`Builder::seed()`] makes the names reproducible.
+///
+/// Closures taking the requested length and returning a `String` implement `NameGenerator`.
+/// They don't get the [`Entropy`], so [`Builder::rng()`] and [`Builder::seed()`] have no
+/// effect on their names; implement the trait instead if that's needed.
+///
+/// See [`Builder::name_with()`] for how to use one.
+///
+/// [`Builder`]: struct.Builder.html
+/// [`Entropy`]: trait.Entropy.html
+/// [`Builder::rng()`]: struct.Builder.html#method.rng
+/// [`Builder::seed()`]: struct.Builder.html#method.seed
+/// [`Builder::name_with()`]: struct.Builder.html#method.name_with
+pub trait NameGenerator {
+    /// Generate a name containing about `len` random characters.
+    ///
+    /// `len` is the value set with [`Builder::rand_bytes()`]. Generators that embed other
+    /// information may return longer names.
+    ///
+    /// [`Builder::rand_bytes()`]: struct.Builder.html#method.rand_bytes
+    fn generate(&self, rng: &mut Entropy, len: usize) -> String;
+}
+
+impl<F> NameGenerator for F
+where
+    F: Fn(usize) -> String,
+{
+    fn generate(&self, _rng: &mut Entropy, len: usize) -> String {
+        self(len)
+    }
+}
+
+const ALPHANUMERIC: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+fn random_chars(rng: &mut Entropy, charset: &[u8], len: usize) -> String {
+    let mut bytes = vec![0; len];
+    rng.fill_bytes(&mut bytes);
+    bytes
+        .iter()
+        .map(|b| charset[*b as usize % charset.len()] as char)
+        .collect()
+}
+
+/// Random letters and digits, like `.tmpA3xq9Z`.
+///
+/// This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Alphanumeric;
+
+impl NameGenerator for Alphanumeric {
+    fn generate(&self, rng: &mut Entropy, len: usize) -> String {
+        random_chars(rng, ALPHANUMERIC, len)
+    }
+}
+
+/// Random lowercase letters and digits.
+///
+/// Unlike [`Alphanumeric`], two names never differ only in case, so the number of distinct
+/// names doesn't shrink on case-insensitive filesystems.
+///
+/// [`Alphanumeric`]: struct.Alphanumeric.html
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lowercase;
+
+impl NameGenerator for Lowercase {
+    fn generate(&self, rng: &mut Entropy, len: usize) -> String {
+        random_chars(rng, &ALPHANUMERIC[..36], len)
+    }
+}
+
+/// Random lowercase hexadecimal digits.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Hex;
+
+impl NameGenerator for Hex {
+    fn generate(&self, rng: &mut Entropy, len: usize) -> String {
+        random_chars(rng, &ALPHANUMERIC[..16], len)
+    }
+}
+
+/// The current time followed by random lowercase letters and digits.
+///
+/// The time is written as the number of seconds and nanoseconds since the Unix epoch, padded
+/// to a fixed width, so names with the same prefix sort in the order they were created.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timestamp;
+
+impl NameGenerator for Timestamp {
+    fn generate(&self, rng: &mut Entropy, len: usize) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        format!(
+            "{:010}{:09}-{}",
+            now.as_secs(),
+            now.subsec_nanos(),
+            Lowercase.generate(rng, len)
+        )
+    }
+}
+
+/// The ID of the current process and the hostname followed by random characters, separated
+/// by dots, like `.tmp4242.myhost.A3xq9Z`.
+///
+/// This makes it possible to tell which process on which machine created a temporary file,
+/// e.g. to clean up after processes that have died with [`Builder::is_orphaned()`] or
+/// [`reap()`].
+///
+/// On Linux, the process ID is followed by a nonce derived from the boot ID and the start time
+/// of the process, like `.tmp4242-3f2a9c1b1d6e0.myhost.A3xq9Z`, so that a new process that
+/// reuses the ID isn't mistaken for the creator.
+///
+/// [`Builder::is_orphaned()`]: struct.Builder.html#method.is_orphaned
+/// [`reap()`]: fn.reap.html
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessId;
+
+impl NameGenerator for ProcessId {
+    fn generate(&self, rng: &mut Entropy, len: usize) -> String {
+        let pid = process_id();
+        let owner = match start_nonce(pid) {
+            Some(nonce) => format!("{}-{}", pid, nonce),
+            None => pid.to_string(),
+        };
+        format!(
+            "{}.{}.{}",
+            owner,
+            hostname(),
+            Alphanumeric.generate(rng, len)
+        )
+    }
+}
+
+// pub(crate)
+// The parts of a name generated by `ProcessId`.
+pub struct Owner<'a> {
+    pub pid: u32,
+    pub nonce: Option<&'a str>,
+    pub host: &'a str,
+    pub random: &'a str,
+}
+
+impl<'a> Owner<'a> {
+    pub fn parse(name: &'a str) -> Option<Owner<'a>> {
+        let mut parts = name.splitn(2, '.');
+        let owner = parts.next()?;
+        let rest = parts.next()?;
+        let dot = rest.rfind('.')?;
+        let mut owner = owner.splitn(2, '-');
+        let pid = owner.next()?;
+        let nonce = owner.next();
+        if pid.is_empty() || !pid.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        if let Some(nonce) = nonce {
+            if nonce.is_empty() || !nonce.bytes().all(|b| b.is_ascii_alphanumeric()) {
+                return None;
+            }
+        }
+        Some(Owner {
+            pid: pid.parse().ok()?,
+            nonce,
+            host: &rest[..dot],
+            random: &rest[dot + 1..],
+        })
+    }
+
+    // Whether the creator is known to have exited. Processes on other hosts, and processes
+    // that can't be checked, are assumed to be alive.
+    pub fn is_dead(&self) -> bool {
+        self.host == hostname() && !is_alive(self.pid, self.nonce)
+    }
+}
+
+#[cfg(unix)]
+fn is_alive(pid: u32, nonce: Option<&str>) -> bool {
+    if pid == 0 || pid > ::libc::pid_t::MAX as u32 {
+        return true;
+    }
+    if unsafe { ::libc::kill(pid as ::libc::pid_t, 0) } != 0 {
+        // `EPERM` means the process exists but belongs to someone else.
+        return io::Error::last_os_error().raw_os_error() != Some(::libc::ESRCH);
+    }
+    match (nonce, start_nonce(pid)) {
+        (Some(expected), Some(actual)) => expected == actual,
+        _ => true,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_alive(_pid: u32, _nonce: Option<&str>) -> bool {
+    true
+}
+
+// The ID of the current boot and the start time of the process, in clock ticks since boot.
+#[cfg(target_os = "linux")]
+fn start_nonce(pid: u32) -> Option<String> {
+    let mut boot_id = String::new();
+    File::open("/proc/sys/kernel/random/boot_id")
+        .and_then(|mut f| f.read_to_string(&mut boot_id))
+        .ok()?;
+    let boot_id: String = boot_id
+        .chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .take(8)
+        .collect();
+
+    let mut stat = String::new();
+    File::open(format!("/proc/{}/stat", pid))
+        .and_then(|mut f| f.read_to_string(&mut stat))
+        .ok()?;
+    // The command name may contain spaces and parentheses, so skip past the last `)`. The
+    // start time is the 22nd field, and the state after the name is the 3rd.
+    let start: u64 = stat[stat.rfind(')')? + 1..]
+        .split_whitespace()
+        .nth(19)?
+        .parse()
+        .ok()?;
+    Some(format!("{}{:x}", boot_id, start))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn start_nonce(_pid: u32) -> Option<String> {
+    None
+}
+
+// pub(crate)
+#[cfg(unix)]
+pub fn process_id() -> u32 {
+    unsafe { ::libc::getpid() as u32 }
+}
+
+// pub(crate)
+#[cfg(windows)]
+pub fn process_id() -> u32 {
+    unsafe { ::winapi::um::processthreadsapi::GetCurrentProcessId() }
+}
+
+// pub(crate)
+#[cfg(target_os = "redox")]
+pub fn process_id() -> u32 {
+    ::syscall::getpid().unwrap_or(0) as u32
+}
+
+#[cfg(unix)]
+fn hostname() -> String {
+    use libc::{c_char, gethostname};
+
+    let mut buf = [0u8; 256];
+    let len = unsafe {
+        if gethostname(buf.as_mut_ptr() as *mut c_char, buf.len()) != 0 {
+            return String::from("localhost");
+        }
+        buf.iter().position(|&b| b == 0).unwrap_or(buf.len())
+    };
+    String::from_utf8_lossy(&buf[..len]).replace('/', "_")
+}
+
+#[cfg(not(unix))]
+fn hostname() -> String {
+    ::std::env::var("COMPUTERNAME").unwrap_or_else(|_| String::from("localhost"))
+}
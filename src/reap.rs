@@ -0,0 +1,178 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use dir;
+use name;
+use util;
+use Builder;
+
+/// Which entries [`reap()`] removes.
+///
+/// [`reap()`]: fn.reap.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReapOptions<'a, 'b> {
+    prefix: &'a str,
+    suffix: &'b str,
+    random_len: usize,
+    adaptive_rand_bytes: bool,
+    older_than: Duration,
+    only_orphaned: bool,
+}
+
+impl<'a, 'b> ReapOptions<'a, 'b> {
+    /// Match the names of the temporary files and directories created by `builder` that were
+    /// last modified at least `age` ago.
+    ///
+    /// The minimum age protects files that are still in use: pick one well above how long
+    /// your temporary files normally live. The age of a directory is that of the directory
+    /// itself, which only changes when entries are added to or removed from it, not when the
+    /// files in it are written to. Entries whose modification time can't be read, or lies in
+    /// the future, are never removed.
+    ///
+    /// The prefix, suffix and random length of the name are taken from `builder`. The random
+    /// part must have been generated by one of the built-in name generators other than
+    /// [`Timestamp`], such as the default [`Alphanumeric`] or [`ProcessId`].
+    ///
+    /// [`Timestamp`]: struct.Timestamp.html
+    /// [`Alphanumeric`]: struct.Alphanumeric.html
+    /// [`ProcessId`]: struct.ProcessId.html
+    pub fn new(builder: &Builder<'a, 'b>, age: Duration) -> Self {
+        ReapOptions {
+            prefix: builder.prefix,
+            suffix: builder.suffix,
+            random_len: builder.random_len,
+            adaptive_rand_bytes: builder.adaptive_rand_bytes,
+            older_than: age,
+            only_orphaned: false,
+        }
+    }
+
+    /// Only remove entries whose owning process is no longer alive.
+    ///
+    /// The owner is only known for names generated by [`ProcessId`]. Entries with other names,
+    /// entries created on a different host, and entries whose owner can't be checked on this
+    /// platform are never removed. Only Unix is supported so far.
+    ///
+    /// This is the same check as [`Builder::is_orphaned()`].
+    ///
+    /// Default: `false`.
+    ///
+    /// [`ProcessId`]: struct.ProcessId.html
+    /// [`Builder::is_orphaned()`]: struct.Builder.html#method.is_orphaned
+    pub fn only_orphaned(&mut self, only_orphaned: bool) -> &mut Self {
+        self.only_orphaned = only_orphaned;
+        self
+    }
+
+    // Whether `name` is the name of a temporary file this may remove.
+    fn matches(&self, name: &str) -> bool {
+        let random = match util::random_part(name, self.prefix, self.suffix) {
+            Some(random) => random,
+            None => return false,
+        };
+        match name::Owner::parse(random) {
+            Some(owner) => {
+                self.matches_random(owner.random) && (!self.only_orphaned || owner.is_dead())
+            }
+            None => !self.only_orphaned && self.matches_random(random),
+        }
+    }
+
+    fn matches_random(&self, random: &str) -> bool {
+        let len_ok = if self.adaptive_rand_bytes {
+            random.len() >= self.random_len
+        } else {
+            random.len() == self.random_len
+        };
+        len_ok && random.bytes().all(|b| b.is_ascii_alphanumeric())
+    }
+
+    fn is_old_enough(&self, metadata: &fs::Metadata) -> bool {
+        let modified = match metadata.modified() {
+            Ok(modified) => modified,
+            Err(_) => return false,
+        };
+        // Fails if modified in the future, e.g. by a host with a different clock.
+        match SystemTime::now().duration_since(modified) {
+            Ok(age) => age >= self.older_than,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Remove stale temporary files and directories from `dir`.
+///
+/// Scans `dir` (not recursively) for entries whose names match the pattern configured by
+/// `options` and removes those that are older than its minimum age and, if requested, whose owning process has
+/// died. Directories are removed with their contents; symlinks are removed, not followed.
+///
+/// This is meant for cleaning up after processes that crashed before deleting their temporary
+/// files. Prefer giving your temporary files a distinct prefix, so that unrelated files in a
+/// shared directory such as `/tmp` aren't mistaken for them.
+///
+/// Returns the paths of the removed entries. Entries that disappear while scanning or can't be
+/// removed for lack of permission are skipped.
+///
+/// # Errors
+///
+/// If `dir` can't be read or an entry can't be removed for another reason, `Err` is returned.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// # use std::io;
+/// # use std::time::Duration;
+/// use tempfile::{Builder, ProcessId, ReapOptions};
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// let mut builder = Builder::new();
+/// builder.prefix("my-service").name_with(ProcessId);
+///
+/// let removed = tempfile::reap(
+///     tempfile::env::temp_dir(),
+///     ReapOptions::new(&builder, Duration::from_secs(24 * 60 * 60)).only_orphaned(true),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn reap<P: AsRef<Path>>(dir: P, options: &ReapOptions) -> io::Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let matches = match entry.file_name().to_str() {
+            Some(name) => options.matches(name),
+            None => false,
+        };
+        if !matches {
+            continue;
+        }
+        let path = entry.path();
+        let result = fs::symlink_metadata(&path).and_then(|metadata| {
+            if !options.is_old_enough(&metadata) {
+                return Ok(false);
+            }
+            if metadata.is_dir() {
+                dir::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+            Ok(true)
+        });
+        match result {
+            Ok(true) => removed.push(path),
+            Ok(false) => {}
+            Err(ref e)
+                if e.kind() == io::ErrorKind::NotFound
+                    || e.kind() == io::ErrorKind::PermissionDenied => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,56 @@
+// Unix system call helpers used by both the file and the directory implementations.
+
+#[cfg(target_os = "linux")]
+use libc::{c_int, c_uint, syscall, SYS_renameat2, AT_FDCWD, EINVAL, ENOSYS};
+#[cfg(target_os = "linux")]
+use libc::{RENAME_EXCHANGE, RENAME_NOREPLACE};
+use std::ffi::CString;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+// Stolen from std.
+pub fn cstr(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contained a null"))
+}
+
+#[cfg(target_os = "linux")]
+fn renameat2(old_path: &Path, new_path: &Path, flags: c_uint) -> io::Result<()> {
+    let old = cstr(old_path)?;
+    let new = cstr(new_path)?;
+    let result = unsafe {
+        syscall(
+            SYS_renameat2,
+            AT_FDCWD,
+            old.as_ptr(),
+            AT_FDCWD,
+            new.as_ptr(),
+            flags,
+        )
+    };
+    if result as c_int == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Rename `old_path` to `new_path`, failing with `AlreadyExists` if something exists there.
+#[cfg(target_os = "linux")]
+pub fn rename_noreplace(old_path: &Path, new_path: &Path) -> io::Result<()> {
+    renameat2(old_path, new_path, RENAME_NOREPLACE)
+}
+
+/// Atomically swap the entries at both paths.
+#[cfg(target_os = "linux")]
+pub fn rename_exchange(old_path: &Path, new_path: &Path) -> io::Result<()> {
+    renameat2(old_path, new_path, RENAME_EXCHANGE)
+}
+
+/// Returns `true` if a `renameat2` flag isn't supported: kernels older than 3.15 don't have
+/// `renameat2` (`ENOSYS`) and not all filesystems support its flags (`EINVAL`).
+#[cfg(target_os = "linux")]
+pub fn is_unsupported(error: &io::Error) -> bool {
+    error.raw_os_error() == Some(ENOSYS) || error.raw_os_error() == Some(EINVAL)
+}
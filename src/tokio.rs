@@ -0,0 +1,599 @@
+//! Temporary files for use with [tokio].
+//!
+//! This module is only available with the `tokio` feature. The blocking parts of creating,
+//! persisting and deleting temporary files and directories run on tokio's blocking thread pool,
+//! so the futures returned here must be run by a tokio runtime. If the runtime shuts down
+//! before such an operation started, the future fails with an error of kind `Other`.
+//!
+//! [tokio]: https://tokio.rs
+
+use std::error;
+use std::fmt;
+use std::future::Future;
+use std::io::{self, SeekFrom};
+use std::mem;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+
+use tokio_rt::fs::File;
+use tokio_rt::io::{AsyncRead, AsyncSeek, AsyncWrite, ReadBuf};
+use tokio_rt::runtime::Handle;
+use tokio_rt::task::{self, JoinError, JoinHandle};
+
+use file;
+use file::PathPersistError;
+use {NamedTempFile, TempDir, TempPath};
+
+/// Create a new temporary file without blocking the runtime.
+///
+/// This is the asynchronous version of [`tempfile()`](../fn.tempfile.html): the file has no
+/// name and is removed by the OS when it's closed.
+///
+/// # Errors
+///
+/// If the file can not be created, `Err` is returned.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// # extern crate tokio;
+/// # use std::io;
+/// use tokio::io::AsyncWriteExt;
+///
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+/// let mut file = runtime.block_on(tempfile::tokio::tempfile())?;
+/// runtime.block_on(file.write_all(b"Brian was here. Briefly."))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn tempfile() -> impl Future<Output = io::Result<File>> {
+    blocking(|| ::tempfile().map(File::from_std))
+}
+
+/// An asynchronous named temporary file.
+///
+/// This is the asynchronous counterpart of [`NamedTempFile`]: it implements tokio's
+/// `AsyncRead`, `AsyncWrite` and `AsyncSeek`, and is deleted when it's dropped unless it's
+/// persisted or kept. The deletion on drop is handed to the blocking thread pool if a runtime
+/// is available.
+///
+/// An existing `NamedTempFile`, e.g. one created with a [`Builder`], can be converted with
+/// `From`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// # extern crate tokio;
+/// # use std::io;
+/// use tempfile::tokio::AsyncNamedTempFile;
+/// use tokio::io::AsyncWriteExt;
+///
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+/// let dir = tempfile::tempdir()?;
+///
+/// let mut file = runtime.block_on(AsyncNamedTempFile::new_in(dir.path()))?;
+/// runtime.block_on(file.write_all(b"Brian was here. Briefly."))?;
+/// runtime.block_on(file.persist(dir.path().join("saved_file.txt")))?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`NamedTempFile`]: ../struct.NamedTempFile.html
+/// [`Builder`]: ../struct.Builder.html
+pub struct AsyncNamedTempFile {
+    path: Offloaded<TempPath>,
+    file: File,
+}
+
+// Drops a temporary file or directory, and so deletes it, on the blocking thread pool if
+// there's a runtime. Only `None` while it's being consumed.
+struct Offloaded<T: Send + 'static>(Option<T>);
+
+impl<T: Send + 'static> Drop for Offloaded<T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.0.take() {
+            match Handle::try_current() {
+                Ok(handle) => {
+                    handle.spawn_blocking(move || drop(value));
+                }
+                Err(_) => drop(value),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for AsyncNamedTempFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AsyncNamedTempFile({:?})", self.path())
+    }
+}
+
+impl AsRef<Path> for AsyncNamedTempFile {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl From<NamedTempFile> for AsyncNamedTempFile {
+    fn from(file: NamedTempFile) -> AsyncNamedTempFile {
+        let (file, path) = file::into_parts(file);
+        AsyncNamedTempFile {
+            path: Offloaded(Some(path)),
+            file: File::from_std(file),
+        }
+    }
+}
+
+impl AsyncNamedTempFile {
+    /// Create a new named temporary file in [`env::temp_dir()`].
+    ///
+    /// See [`NamedTempFile::new()`] for the security considerations.
+    ///
+    /// [`env::temp_dir()`]: ../env/fn.temp_dir.html
+    /// [`NamedTempFile::new()`]: ../struct.NamedTempFile.html#method.new
+    pub fn new() -> impl Future<Output = io::Result<AsyncNamedTempFile>> {
+        blocking(|| NamedTempFile::new().map(AsyncNamedTempFile::from))
+    }
+
+    /// Create a new named temporary file in the specified directory.
+    ///
+    /// See [`NamedTempFile::new()`] for the security considerations.
+    ///
+    /// [`NamedTempFile::new()`]: ../struct.NamedTempFile.html#method.new
+    pub fn new_in<P: AsRef<Path>>(dir: P) -> impl Future<Output = io::Result<AsyncNamedTempFile>> {
+        let dir = dir.as_ref().to_owned();
+        blocking(move || NamedTempFile::new_in(dir).map(AsyncNamedTempFile::from))
+    }
+
+    /// Get the temporary file's path.
+    pub fn path(&self) -> &Path {
+        self.temp_path()
+    }
+
+    /// Keep the temporary file instead of deleting it when it's dropped or closed.
+    ///
+    /// See [`TempPath::keep()`](../struct.TempPath.html#method.keep).
+    pub fn keep(&mut self, keep: bool) {
+        self.path.0.as_mut().expect("path taken").keep(keep)
+    }
+
+    /// Get a reference to the underlying file.
+    pub fn as_file(&self) -> &File {
+        &self.file
+    }
+
+    /// Get a mutable reference to the underlying file.
+    pub fn as_file_mut(&mut self) -> &mut File {
+        &mut self.file
+    }
+
+    /// Close and remove the temporary file.
+    ///
+    /// Use this if you want to detect errors in deleting the file.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be deleted, `Err` is returned.
+    pub fn close(mut self) -> impl Future<Output = io::Result<()>> {
+        let path = self.take_path();
+        blocking(move || path.close())
+    }
+
+    /// Persist the temporary file at the target path, replacing any file that exists there.
+    ///
+    /// Pending writes are flushed first. On success, the file is returned and no longer
+    /// deleted. If this fails, the temporary file is returned in the [`AsyncPersistError`].
+    ///
+    /// See [`NamedTempFile::persist()`] for the details.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be flushed or moved to the new location, `Err` is returned.
+    ///
+    /// [`AsyncPersistError`]: struct.AsyncPersistError.html
+    /// [`NamedTempFile::persist()`]: ../struct.NamedTempFile.html#method.persist
+    pub fn persist<P: AsRef<Path>>(
+        self,
+        new_path: P,
+    ) -> impl Future<Output = Result<File, AsyncPersistError>> {
+        Persist::new(self, new_path.as_ref(), true)
+    }
+
+    /// Persist the temporary file at the target path iff no file exists there.
+    ///
+    /// Like [`persist()`](#method.persist), but fails if a file exists at the target path. See
+    /// [`NamedTempFile::persist_noclobber()`] for the details.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be flushed or moved to the new location or a file already exists
+    /// there, `Err` is returned.
+    ///
+    /// [`NamedTempFile::persist_noclobber()`]: ../struct.NamedTempFile.html#method.persist_noclobber
+    pub fn persist_noclobber<P: AsRef<Path>>(
+        self,
+        new_path: P,
+    ) -> impl Future<Output = Result<File, AsyncPersistError>> {
+        Persist::new(self, new_path.as_ref(), false)
+    }
+
+    /// Convert the temporary file into a `tokio::fs::File`.
+    ///
+    /// The inner file will be deleted.
+    pub fn into_file(self) -> File {
+        self.file
+    }
+
+    /// Close the file, leaving only the temporary file path.
+    pub fn into_temp_path(mut self) -> TempPath {
+        self.take_path()
+    }
+
+    fn temp_path(&self) -> &TempPath {
+        self.path.0.as_ref().expect("path taken")
+    }
+
+    fn take_path(&mut self) -> TempPath {
+        self.path.0.take().expect("path taken")
+    }
+}
+
+impl AsyncRead for AsyncNamedTempFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for AsyncNamedTempFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.file).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_shutdown(cx)
+    }
+}
+
+impl AsyncSeek for AsyncNamedTempFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        Pin::new(&mut self.file).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.file).poll_complete(cx)
+    }
+}
+
+/// Error returned when persisting an asynchronous temporary file fails.
+#[derive(Debug)]
+pub struct AsyncPersistError {
+    /// The underlying IO error.
+    pub error: io::Error,
+    /// The temporary file that couldn't be persisted.
+    pub file: AsyncNamedTempFile,
+}
+
+impl From<AsyncPersistError> for io::Error {
+    #[inline]
+    fn from(error: AsyncPersistError) -> io::Error {
+        error.error
+    }
+}
+
+impl From<AsyncPersistError> for AsyncNamedTempFile {
+    #[inline]
+    fn from(error: AsyncPersistError) -> AsyncNamedTempFile {
+        error.file
+    }
+}
+
+impl fmt::Display for AsyncPersistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to persist temporary file: {}", self.error)
+    }
+}
+
+impl error::Error for AsyncPersistError {
+    fn description(&self) -> &str {
+        "failed to persist temporary file"
+    }
+    fn cause(&self) -> Option<&error::Error> {
+        Some(&self.error)
+    }
+}
+
+/// An asynchronous temporary directory.
+///
+/// This wraps a [`TempDir`] so that it can be created, closed and dropped inside a runtime
+/// without blocking it: removing a directory and its contents can take a while, so it's done on
+/// the blocking thread pool. Like [`AsyncNamedTempFile`], the directory is removed right away
+/// if it's dropped outside of a runtime.
+///
+/// An existing `TempDir`, e.g. one created with a [`Builder`], can be converted with `From`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// # extern crate tokio;
+/// # use std::io;
+/// use tempfile::tokio::AsyncTempDir;
+///
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+///
+/// let dir = runtime.block_on(AsyncTempDir::new())?;
+/// let file_path = dir.path().join("my-temporary-note.txt");
+/// runtime.block_on(tokio::fs::File::create(file_path))?;
+/// runtime.block_on(dir.close())?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`TempDir`]: ../struct.TempDir.html
+/// [`AsyncNamedTempFile`]: struct.AsyncNamedTempFile.html
+/// [`Builder`]: ../struct.Builder.html
+pub struct AsyncTempDir {
+    dir: Offloaded<TempDir>,
+}
+
+impl fmt::Debug for AsyncTempDir {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AsyncTempDir({:?})", self.path())
+    }
+}
+
+impl AsRef<Path> for AsyncTempDir {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl From<TempDir> for AsyncTempDir {
+    fn from(dir: TempDir) -> AsyncTempDir {
+        AsyncTempDir {
+            dir: Offloaded(Some(dir)),
+        }
+    }
+}
+
+impl AsyncTempDir {
+    /// Create a new temporary directory in [`env::temp_dir()`].
+    ///
+    /// [`env::temp_dir()`]: ../env/fn.temp_dir.html
+    pub fn new() -> impl Future<Output = io::Result<AsyncTempDir>> {
+        blocking(|| TempDir::new().map(AsyncTempDir::from))
+    }
+
+    /// Create a new temporary directory in the specified directory.
+    pub fn new_in<P: AsRef<Path>>(dir: P) -> impl Future<Output = io::Result<AsyncTempDir>> {
+        let dir = dir.as_ref().to_owned();
+        blocking(move || TempDir::new_in(dir).map(AsyncTempDir::from))
+    }
+
+    /// Get the path of the temporary directory.
+    pub fn path(&self) -> &Path {
+        self.temp_dir().path()
+    }
+
+    /// Keep the temporary directory instead of removing it when it's dropped or closed.
+    ///
+    /// See [`TempDir::keep()`](../struct.TempDir.html#method.keep).
+    pub fn keep(&mut self, keep: bool) {
+        self.dir.0.as_mut().expect("dir taken").keep(keep)
+    }
+
+    /// Remove the temporary directory and its contents.
+    ///
+    /// Use this if you want to detect errors in removing the directory.
+    ///
+    /// # Errors
+    ///
+    /// If the directory or its contents can't be removed, `Err` is returned.
+    pub fn close(mut self) -> impl Future<Output = io::Result<()>> {
+        let dir = self.take_dir();
+        blocking(move || dir.close())
+    }
+
+    /// Convert into the underlying [`TempDir`].
+    ///
+    /// [`TempDir`]: ../struct.TempDir.html
+    pub fn into_temp_dir(mut self) -> TempDir {
+        self.take_dir()
+    }
+
+    fn temp_dir(&self) -> &TempDir {
+        self.dir.0.as_ref().expect("dir taken")
+    }
+
+    fn take_dir(&mut self) -> TempDir {
+        self.dir.0.take().expect("dir taken")
+    }
+}
+
+// The result of a blocking task, re-raising its panic if it panicked. Blocking tasks are only
+// cancelled if the runtime shuts down before they start.
+fn join<T>(result: Result<T, JoinError>) -> io::Result<T> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(e) => {
+            if e.is_panic() {
+                panic::resume_unwind(e.into_panic())
+            }
+            Err(io::Error::new(io::ErrorKind::Other, e))
+        }
+    }
+}
+
+// A blocking operation, started on the blocking thread pool when first polled so that the
+// future can be created outside of a runtime.
+enum Blocking<F, T> {
+    Start(F),
+    Running(JoinHandle<io::Result<T>>),
+    Done,
+}
+
+fn blocking<F, T>(f: F) -> Blocking<F, T>
+where
+    F: FnOnce() -> io::Result<T> + Send + Unpin + 'static,
+    T: Send + 'static,
+{
+    Blocking::Start(f)
+}
+
+impl<F, T> Future for Blocking<F, T>
+where
+    F: FnOnce() -> io::Result<T> + Send + Unpin + 'static,
+    T: Send + 'static,
+{
+    type Output = io::Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<T>> {
+        loop {
+            match mem::replace(&mut *self, Blocking::Done) {
+                Blocking::Start(f) => *self = Blocking::Running(task::spawn_blocking(f)),
+                Blocking::Running(mut handle) => match Pin::new(&mut handle).poll(cx) {
+                    Poll::Ready(result) => return Poll::Ready(join(result).and_then(|r| r)),
+                    Poll::Pending => {
+                        *self = Blocking::Running(handle);
+                        return Poll::Pending;
+                    }
+                },
+                Blocking::Done => panic!("`Blocking` polled after completion"),
+            }
+        }
+    }
+}
+
+// Flushes the file and then renames it on the blocking thread pool.
+enum Persist {
+    Flushing {
+        file: AsyncNamedTempFile,
+        new_path: PathBuf,
+        overwrite: bool,
+    },
+    Renaming {
+        file: AsyncNamedTempFile,
+        // Holds the path until the rename starts, so that it isn't lost if it never does.
+        path: Arc<Mutex<Option<TempPath>>>,
+        rename: JoinHandle<Result<(), PathPersistError>>,
+    },
+    Done,
+}
+
+impl Persist {
+    fn new(file: AsyncNamedTempFile, new_path: &Path, overwrite: bool) -> Persist {
+        Persist::Flushing {
+            file,
+            new_path: new_path.to_owned(),
+            overwrite,
+        }
+    }
+}
+
+impl Future for Persist {
+    type Output = Result<File, AsyncPersistError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        loop {
+            match mem::replace(&mut *self, Persist::Done) {
+                Persist::Flushing {
+                    mut file,
+                    new_path,
+                    overwrite,
+                } => match Pin::new(&mut file.file).poll_flush(cx) {
+                    Poll::Ready(Ok(())) => {
+                        let path = Arc::new(Mutex::new(Some(file.take_path())));
+                        let task_path = path.clone();
+                        let rename = task::spawn_blocking(move || {
+                            let path = lock(&task_path).take().expect("path taken");
+                            if overwrite {
+                                path.persist(new_path)
+                            } else {
+                                path.persist_noclobber(new_path)
+                            }
+                        });
+                        *self = Persist::Renaming { file, path, rename };
+                    }
+                    Poll::Ready(Err(error)) => {
+                        return Poll::Ready(Err(AsyncPersistError { error, file }))
+                    }
+                    Poll::Pending => {
+                        *self = Persist::Flushing {
+                            file,
+                            new_path,
+                            overwrite,
+                        };
+                        return Poll::Pending;
+                    }
+                },
+                Persist::Renaming {
+                    mut file,
+                    path,
+                    mut rename,
+                } => match Pin::new(&mut rename).poll(cx) {
+                    Poll::Ready(result) => {
+                        return Poll::Ready(match join(result) {
+                            Ok(Ok(())) => Ok(file.into_file()),
+                            Ok(Err(e)) => {
+                                file.path.0 = Some(e.path);
+                                Err(AsyncPersistError {
+                                    error: e.error,
+                                    file,
+                                })
+                            }
+                            Err(error) => {
+                                file.path.0 = lock(&path).take();
+                                Err(AsyncPersistError { error, file })
+                            }
+                        })
+                    }
+                    Poll::Pending => {
+                        *self = Persist::Renaming { file, path, rename };
+                        return Poll::Pending;
+                    }
+                },
+                Persist::Done => panic!("`Persist` polled after completion"),
+            }
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // Nothing panics while holding the lock.
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
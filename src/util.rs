@@ -1,52 +1,204 @@
-use rand;
-use rand::Rng;
-use std::ffi::OsString;
-use std::path::{Path, PathBuf};
-use std::{io, iter};
-
-fn tmpname(prefix: &str, suffix: &str, rand_len: usize) -> OsString {
-    let mut buf = String::with_capacity(prefix.len() + suffix.len() + rand_len);
-    buf.push_str(prefix);
-    buf.extend(iter::repeat('X').take(rand_len));
-    buf.push_str(suffix);
-
-    // Randomize.
-    unsafe {
-        // We guarantee utf8.
-        let bytes = &mut buf.as_mut_vec()[prefix.len()..prefix.len() + rand_len];
-        rand::thread_rng().fill_bytes(bytes);
-        for byte in bytes.iter_mut() {
-            *byte = match *byte % 62 {
-                v @ 0...9 => (v + b'0'),
-                v @ 10...35 => (v - 10 + b'a'),
-                v @ 36...61 => (v - 36 + b'A'),
-                _ => unreachable!(),
-            }
-        }
-    }
-    OsString::from(buf)
+use std::ffi::{OsStr, OsString};
+use std::io::Write;
+use std::path::{self, Path, PathBuf};
+use std::{env, error, fmt, io};
+
+use name;
+use Builder;
+
+pub fn tmpname(builder: &Builder, rand_len: usize) -> OsString {
+    let mut buf = OsString::from(builder.prefix);
+    buf.push(name::with_entropy(builder.rng.as_deref(), |rng| {
+        builder.names().generate(rng, rand_len)
+    }));
+    buf.push(builder.suffix);
+    buf
 }
 
-pub fn create_helper<F, R>(
-    base: &Path,
-    prefix: &str,
-    suffix: &str,
-    random_len: usize,
-    f: F,
-) -> io::Result<R>
+// With `Builder::adaptive_rand_bytes`, the random part grows by one character after this many
+// consecutive collisions.
+const COLLISIONS_PER_CHAR: u32 = 4;
+
+pub fn create_helper<F, R>(base: &Path, builder: &Builder, f: F) -> io::Result<R>
 where
     F: Fn(PathBuf) -> io::Result<R>,
 {
-    for _ in 0..::NUM_RETRIES {
-        let path = base.join(tmpname(prefix, suffix, random_len));
-        return match f(path) {
-            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
-            res => res,
-        };
+    create_helper_named(base, builder, |_, path| f(path))
+}
+
+// Like `create_helper`, but also passes the generated name, relative to `base`, to `f`.
+pub fn create_helper_named<F, R>(base: &Path, builder: &Builder, f: F) -> io::Result<R>
+where
+    F: Fn(&Path, PathBuf) -> io::Result<R>,
+{
+    let mut random_len = builder.random_len;
+    let mut attempts = 0;
+    loop {
+        let name = tmpname(builder, random_len);
+        check_name(&name)?;
+        let path = base.join(&name);
+        attempts += 1;
+        match f(Path::new(&name), path) {
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            res => return res,
+        }
+        if attempts >= builder.retries {
+            break;
+        }
+        if builder.adaptive_rand_bytes && attempts % COLLISIONS_PER_CHAR == 0 {
+            random_len += 1;
+        }
     }
 
     Err(io::Error::new(
         io::ErrorKind::AlreadyExists,
-        "too many temporary files exist",
+        CollisionError {
+            attempts,
+            dir: base.to_owned(),
+            pattern: format!(
+                "{}{}{}",
+                builder.prefix,
+                "X".repeat(random_len),
+                builder.suffix
+            ),
+        },
     ))
 }
+
+// Generated names must name an entry directly inside the directory they're created in.
+fn check_name(name: &OsStr) -> io::Result<()> {
+    let valid = !name.is_empty()
+        && name != OsStr::new(".")
+        && name != OsStr::new("..")
+        && !name.to_string_lossy().chars().any(path::is_separator);
+    if valid {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "invalid temporary file name {:?}: it must not be empty, `.` or `..` or \
+                 contain a path separator",
+                name
+            ),
+        ))
+    }
+}
+
+/// The error returned when no unused name for a temporary file or directory could be found.
+///
+/// It's returned wrapped in an [`io::Error`] of kind `AlreadyExists` and can be retrieved with
+/// [`io::Error::get_ref()`] and `downcast_ref`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate tempfile;
+/// # use std::io;
+/// # fn main() {
+/// #     if let Err(_) = run() {
+/// #         ::std::process::exit(1);
+/// #     }
+/// # }
+/// # fn run() -> Result<(), io::Error> {
+/// use tempfile::{Builder, CollisionError};
+///
+/// let dir = tempfile::tempdir()?;
+/// let mut builder = Builder::new();
+/// builder.rand_bytes(0).retries(3);
+///
+/// let _first = builder.tempfile_in(dir.path())?;
+/// let err = builder.tempfile_in(dir.path()).unwrap_err();
+///
+/// let collision = err
+///     .get_ref()
+///     .and_then(|e| e.downcast_ref::<CollisionError>())
+///     .unwrap();
+/// assert_eq!(collision.attempts(), 3);
+/// assert_eq!(collision.pattern(), ".tmp");
+/// assert_eq!(collision.dir(), dir.path());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`io::Error`]: https://doc.rust-lang.org/std/io/struct.Error.html
+/// [`io::Error::get_ref()`]: https://doc.rust-lang.org/std/io/struct.Error.html#method.get_ref
+#[derive(Debug)]
+pub struct CollisionError {
+    attempts: u32,
+    dir: PathBuf,
+    pattern: String,
+}
+
+impl CollisionError {
+    /// The number of names that were tried.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// The directory the file or directory was to be created in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The pattern of the names that were tried, with the generated part replaced by `X`s.
+    ///
+    /// With [`Builder::adaptive_rand_bytes()`], this is the pattern of the last name.
+    ///
+    /// [`Builder::adaptive_rand_bytes()`]: struct.Builder.html#method.adaptive_rand_bytes
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+}
+
+impl fmt::Display for CollisionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "too many temporary files exist: tried {} names like {:?} in {}",
+            self.attempts,
+            self.pattern,
+            self.dir.display()
+        )
+    }
+}
+
+impl error::Error for CollisionError {
+    fn description(&self) -> &str {
+        "too many temporary files exist"
+    }
+}
+
+/// The part of `name` between `prefix` and `suffix`, if it has them.
+pub fn random_part<'n>(name: &'n str, prefix: &str, suffix: &str) -> Option<&'n str> {
+    if name.len() < prefix.len() + suffix.len()
+        || !name.starts_with(prefix)
+        || !name.ends_with(suffix)
+    {
+        return None;
+    }
+    Some(&name[prefix.len()..name.len() - suffix.len()])
+}
+
+/// The directory containing `path`, or `.` if `path` is a bare file name.
+pub fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Returns `true` if the temporary file or directory at `path` should be left in place rather
+/// than deleted, either because it was asked to be kept or because `TEMPFILE_KEEP` is set.
+pub fn should_keep(keep: bool, path: &Path) -> bool {
+    if keep {
+        return true;
+    }
+    match env::var_os("TEMPFILE_KEEP") {
+        Some(ref v) if !v.is_empty() && v.as_os_str() != OsStr::new("0") => {
+            let _ = writeln!(io::stderr(), "tempfile: keeping {}", path.display());
+            true
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,60 @@
+extern crate tempfile;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use tempfile::{tempdir, write_atomic, AtomicWriteFile};
+
+fn read<P: AsRef<Path>>(path: P) -> String {
+    let mut buf = String::new();
+    File::open(path).unwrap().read_to_string(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn test_commit() {
+    let dir = tempdir().unwrap();
+    let target = dir.path().join("target");
+    write_atomic(&target, "abcde").unwrap();
+
+    let mut file = AtomicWriteFile::open(&target).unwrap();
+    write!(file, "fghij").unwrap();
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+    assert_eq!("abcde", read(&target));
+
+    file.commit().unwrap();
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    assert_eq!("fghij", read(&target));
+}
+
+#[test]
+fn test_discard() {
+    let dir = tempdir().unwrap();
+    let target = dir.path().join("target");
+    write_atomic(&target, "abcde").unwrap();
+
+    {
+        let mut file = AtomicWriteFile::open(&target).unwrap();
+        write!(file, "fghij").unwrap();
+    }
+    let mut file = AtomicWriteFile::open(&target).unwrap();
+    write!(file, "fghij").unwrap();
+    file.discard().unwrap();
+
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    assert_eq!("abcde", read(&target));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let target = dir.path().join("target");
+    write_atomic(&target, "abcde").unwrap();
+    fs::set_permissions(&target, fs::Permissions::from_mode(0o640)).unwrap();
+
+    write_atomic(&target, "fghij").unwrap();
+    let mode = fs::metadata(&target).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+}
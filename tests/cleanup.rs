@@ -0,0 +1,146 @@
+#![cfg(unix)]
+
+extern crate libc;
+extern crate tempfile;
+
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{self, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use tempfile::{Builder, NamedTempFile, TempDir};
+
+// Runs in the child process: create some temporary files, then either exit or wait to be
+// killed.
+fn child(dir: &Path, exit: bool) {
+    tempfile::cleanup_on_exit().unwrap();
+
+    let tmpdir = tempfile::tempdir_in(dir).unwrap();
+    fs::File::create(tmpdir.path().join("file")).unwrap();
+    let _tmpfile = NamedTempFile::new_in(dir).unwrap();
+    let _kept = Builder::new()
+        .prefix("kept")
+        .keep(true)
+        .tempfile_in(dir)
+        .unwrap();
+    NamedTempFile::new_in(dir)
+        .unwrap()
+        .persist(dir.join("persisted"))
+        .unwrap();
+
+    if exit {
+        process::exit(0);
+    }
+    println!("ready");
+    std::io::stdout().flush().unwrap();
+    loop {
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+// Runs in the child process: create a temporary file by a relative path, then change the working
+// directory to one holding a file by the same relative path, which must survive the cleanup.
+fn relative_child(dir: &Path) {
+    tempfile::cleanup_on_exit().unwrap();
+
+    env::set_current_dir(dir).unwrap();
+    let tmpfile = NamedTempFile::new_in(".").unwrap();
+    fs::create_dir("moved").unwrap();
+    env::set_current_dir("moved").unwrap();
+    fs::File::create(tmpfile.path()).unwrap();
+    process::exit(0);
+}
+
+// Runs in the child process: fork, and let the forked process exit while the temporary file is
+// still in use.
+fn fork_child(dir: &Path) {
+    tempfile::cleanup_on_exit().unwrap();
+
+    let tmpfile = NamedTempFile::new_in(dir).unwrap();
+    match unsafe { libc::fork() } {
+        -1 => panic!("fork failed"),
+        0 => process::exit(0),
+        pid => {
+            let mut status = 0;
+            assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+        }
+    }
+    assert!(tmpfile.path().exists());
+    process::exit(0);
+}
+
+fn remaining(dir: &Path) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .map(|name| {
+            if name.starts_with("kept") {
+                "kept".to_owned()
+            } else {
+                name
+            }
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+fn spawn(dir: &Path, how: &str) -> process::Child {
+    Command::new(env::current_exe().unwrap())
+        .arg("test_cleanup_on_exit")
+        .arg("--nocapture")
+        .env("TEMPFILE_CLEANUP_DIR", dir)
+        .env("TEMPFILE_CLEANUP_HOW", how)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap()
+}
+
+#[test]
+fn test_cleanup_on_exit() {
+    if let Some(dir) = env::var_os("TEMPFILE_CLEANUP_DIR") {
+        let how = env::var_os("TEMPFILE_CLEANUP_HOW").unwrap();
+        if how == "relative" {
+            return relative_child(Path::new(&dir));
+        }
+        if how == "fork" {
+            return fork_child(Path::new(&dir));
+        }
+        return child(Path::new(&dir), how == "exit");
+    }
+
+    let dir = TempDir::new().unwrap();
+    assert!(spawn(dir.path(), "exit").wait().unwrap().success());
+    assert_eq!(remaining(dir.path()), vec!["kept", "persisted"]);
+
+    let dir = TempDir::new().unwrap();
+    let mut child = spawn(dir.path(), "signal");
+    let mut line = String::new();
+    {
+        let mut stdout = BufReader::new(child.stdout.as_mut().unwrap());
+        while !line.contains("ready") {
+            line.clear();
+            assert!(stdout.read_line(&mut line).unwrap() > 0);
+        }
+    }
+    let status = Command::new("kill")
+        .arg("-TERM")
+        .arg(child.id().to_string())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(!child.wait().unwrap().success());
+    assert_eq!(remaining(dir.path()), vec!["kept", "persisted"]);
+
+    let dir = TempDir::new().unwrap();
+    assert!(spawn(dir.path(), "relative").wait().unwrap().success());
+    assert_eq!(remaining(dir.path()), vec!["moved"]);
+    assert_eq!(remaining(&dir.path().join("moved")).len(), 1);
+
+    let dir = TempDir::new().unwrap();
+    assert!(spawn(dir.path(), "fork").wait().unwrap().success());
+    assert!(remaining(dir.path()).is_empty());
+}
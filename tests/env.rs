@@ -0,0 +1,123 @@
+extern crate tempfile;
+
+use std::env;
+use std::fs;
+
+use tempfile::{Builder, NamedTempFile, TempDir};
+
+fn keep_all() {
+    env::set_var("TEMPFILE_KEEP", "1");
+    let tmpfile = NamedTempFile::new().unwrap();
+    let tmpdir = TempDir::new().unwrap();
+    let file_path = tmpfile.path().to_path_buf();
+    let dir_path = tmpdir.path().to_path_buf();
+    drop(tmpfile);
+    drop(tmpdir);
+    env::remove_var("TEMPFILE_KEEP");
+
+    assert!(fs::metadata(&file_path).is_ok());
+    assert!(fs::metadata(&dir_path).is_ok());
+    fs::remove_file(&file_path).unwrap();
+    fs::remove_dir(&dir_path).unwrap();
+
+    env::set_var("TEMPFILE_KEEP", "0");
+    let tmpfile = NamedTempFile::new().unwrap();
+    let file_path = tmpfile.path().to_path_buf();
+    drop(tmpfile);
+    env::remove_var("TEMPFILE_KEEP");
+    assert!(fs::metadata(&file_path).is_err());
+}
+
+fn zeros(dest: &mut [u8]) {
+    for byte in dest.iter_mut() {
+        *byte = 0;
+    }
+}
+
+fn counter(dest: &mut [u8]) {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    for byte in dest.iter_mut() {
+        *byte = COUNTER.fetch_add(1, Ordering::SeqCst) as u8;
+    }
+}
+
+fn entropy_source() {
+    let dir = TempDir::new().unwrap();
+    tempfile::set_entropy_source(zeros);
+    let tmpdir = tempfile::tempdir_in(dir.path()).unwrap();
+    assert_eq!(tmpdir.path().file_name().unwrap(), ".tmp000000");
+
+    // The builder's own source takes precedence.
+    let tmpfile = Builder::new().seed(1).tempfile_in(dir.path()).unwrap();
+    assert!(tmpfile.path().file_name().unwrap() != ".tmp000000");
+
+    // Leave a source behind that doesn't always produce the same name.
+    tempfile::set_entropy_source(counter);
+}
+
+fn tmpdir_variable() {
+    let dir = TempDir::new().unwrap();
+    env::set_var("TEMPFILE_TMPDIR", dir.path());
+    let tmpfile = NamedTempFile::new().unwrap();
+    let tmpdir = TempDir::new().unwrap();
+    env::remove_var("TEMPFILE_TMPDIR");
+
+    assert_eq!(tempfile::env::temp_dir(), env::temp_dir());
+    assert_eq!(tmpfile.path().parent(), Some(dir.path()));
+    assert_eq!(tmpdir.path().parent(), Some(dir.path()));
+}
+
+// Sockets fall back to /tmp if names don't fit into `sun_path` in the temporary directory.
+#[cfg(unix)]
+fn socket_dir_fallback() {
+    let dir = TempDir::new().unwrap();
+    let long = dir.path().join("x".repeat(120));
+    fs::create_dir(&long).unwrap();
+    env::set_var("TEMPFILE_TMPDIR", &long);
+    let server = Builder::new().unix_listener();
+    env::remove_var("TEMPFILE_TMPDIR");
+
+    let server = server.unwrap();
+    assert_eq!(server.path().parent(), Some(std::path::Path::new("/tmp")));
+
+    // Names longer than expected only turn out not to fit when binding.
+    let deep = dir.path().join("x".repeat(20));
+    fs::create_dir(&deep).unwrap();
+    env::set_var("TEMPFILE_TMPDIR", &deep);
+    let server = Builder::new()
+        .name_with(|len: usize| "x".repeat(len + 80))
+        .unix_listener();
+    env::remove_var("TEMPFILE_TMPDIR");
+
+    let server = server.unwrap();
+    assert_eq!(server.path().parent(), Some(std::path::Path::new("/tmp")));
+}
+
+// Must run last, the override can't be undone.
+fn override_temp_dir() {
+    let dir = TempDir::new().unwrap();
+    let other = TempDir::new().unwrap();
+    tempfile::env::override_temp_dir(dir.path()).unwrap();
+
+    // The override takes precedence over the environment variable.
+    env::set_var("TEMPFILE_TMPDIR", other.path());
+    assert_eq!(tempfile::env::temp_dir(), dir.path());
+    let tmpfile = Builder::new().tempfile().unwrap();
+    assert_eq!(tmpfile.path().parent(), Some(dir.path()));
+    env::remove_var("TEMPFILE_TMPDIR");
+
+    assert!(tempfile::env::override_temp_dir(other.path()).is_err());
+    assert_eq!(tempfile::env::temp_dir(), dir.path());
+}
+
+// The tests modify the environment of the whole process so they can't run in parallel.
+#[test]
+fn main() {
+    keep_all();
+    entropy_source();
+    tmpdir_variable();
+    #[cfg(unix)]
+    socket_dir_fallback();
+    override_temp_dir();
+}
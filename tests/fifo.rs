@@ -0,0 +1,73 @@
+#![cfg(unix)]
+
+extern crate tempfile;
+
+use std::fs;
+use std::io::{Read, Write};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::thread;
+use tempfile::{Builder, NamedTempFifo};
+
+#[test]
+fn test_fifo() {
+    let dir = tempfile::tempdir().unwrap();
+    let fifo = NamedTempFifo::new_in(dir.path()).unwrap();
+    let path = fifo.path().to_owned();
+    let metadata = fs::metadata(&path).unwrap();
+    assert!(metadata.file_type().is_fifo());
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+
+    let writer = {
+        let path = path.clone();
+        thread::spawn(move || {
+            let mut input = fs::OpenOptions::new().write(true).open(path).unwrap();
+            input.write_all(b"abcde").unwrap();
+        })
+    };
+    let mut buf = String::new();
+    fifo.open_read().unwrap().read_to_string(&mut buf).unwrap();
+    writer.join().unwrap();
+    assert_eq!(buf, "abcde");
+
+    // Both ends from the same `NamedTempFifo`.
+    let reader = {
+        let path = path.clone();
+        thread::spawn(move || {
+            let mut buf = String::new();
+            fs::File::open(path)
+                .unwrap()
+                .read_to_string(&mut buf)
+                .unwrap();
+            buf
+        })
+    };
+    fifo.open_write().unwrap().write_all(b"fghij").unwrap();
+    assert_eq!(reader.join().unwrap(), "fghij");
+
+    drop(fifo);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_builder_fifo() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut fifo = Builder::new()
+        .prefix("input")
+        .suffix(".pipe")
+        .fifo_in(dir.path())
+        .unwrap();
+    let path = fifo.path().to_owned();
+    let name = path.file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("input"));
+    assert!(name.ends_with(".pipe"));
+
+    fifo.keep(true);
+    drop(fifo);
+    assert!(path.exists());
+    fs::remove_file(&path).unwrap();
+
+    let fifo = Builder::new().fifo_in(dir.path()).unwrap();
+    let path = fifo.path().to_owned();
+    fifo.close().unwrap();
+    assert!(!path.exists());
+}
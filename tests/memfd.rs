@@ -0,0 +1,60 @@
+#![cfg(target_os = "linux")]
+
+extern crate tempfile;
+
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use tempfile::{Builder, Seals};
+
+#[test]
+fn test_memfd() {
+    let mut file = tempfile::tempfile_memfd("test").unwrap();
+    file.write_all(b"abcde").unwrap();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = String::new();
+    file.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+
+    let link = fs::read_link(format!("/proc/self/fd/{}", file.as_raw_fd())).unwrap();
+    assert!(link.to_str().unwrap().starts_with("/memfd:test"));
+
+    assert!(tempfile::tempfile_memfd("te\0st").is_err());
+}
+
+#[test]
+fn test_seal() {
+    let mut file = tempfile::tempfile_memfd("sealed").unwrap();
+    file.write_all(b"abcde").unwrap();
+    assert!(file.seals().unwrap().is_empty());
+
+    file.seal(Seals::WRITE | Seals::SHRINK | Seals::GROW)
+        .unwrap();
+    let seals = file.seals().unwrap();
+    assert!(seals.contains(Seals::WRITE | Seals::SHRINK));
+    assert!(!seals.contains(Seals::SEAL));
+
+    assert!(file.write_all(b"fghij").is_err());
+    assert!(file.as_file().set_len(2).is_err());
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = String::new();
+    file.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+
+    file.seal(Seals::SEAL).unwrap();
+    assert!(file.seal(Seals::empty()).is_err());
+}
+
+#[test]
+fn test_builder_memfd() {
+    let file = Builder::new()
+        .prefix("blob-")
+        .suffix(".bin")
+        .rand_bytes(4)
+        .memfd()
+        .unwrap();
+    let link = fs::read_link(format!("/proc/self/fd/{}", file.as_raw_fd())).unwrap();
+    let name = link.to_str().unwrap();
+    assert!(name.starts_with("/memfd:blob-"));
+    assert!(name.contains(".bin"));
+}
@@ -1,9 +1,11 @@
 extern crate tempfile;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::Path;
-use tempfile::{Builder, NamedTempFile};
+use tempfile::{
+    Builder, CollisionError, Hex, NamedTempFile, PersistStrategy, ProcessId, Timestamp,
+};
 
 fn exists<P: AsRef<Path>>(path: P) -> bool {
     std::fs::metadata(path.as_ref()).is_ok()
@@ -81,6 +83,34 @@ fn test_persist_noclobber() {
     std::fs::remove_file(&persist_path).unwrap();
 }
 
+#[test]
+fn test_persist_noclobber_with_strategy() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("persisted");
+
+    let tmpfile = NamedTempFile::new_in(dir.path()).unwrap();
+    let old_path = tmpfile.path().to_path_buf();
+    let (_, strategy) = tmpfile.persist_noclobber_with_strategy(&target).unwrap();
+    assert!(!exists(&old_path));
+    if cfg!(target_os = "linux") {
+        // Falls back on hard links on filesystems without `RENAME_NOREPLACE`.
+        assert!(strategy != PersistStrategy::Rename);
+    } else if cfg!(windows) {
+        assert_eq!(strategy, PersistStrategy::RenameNoReplace);
+    } else {
+        assert_eq!(strategy, PersistStrategy::LinkUnlink);
+    }
+
+    let path = NamedTempFile::new_in(dir.path()).unwrap().into_temp_path();
+    let err = path.persist_noclobber_with_strategy(&target).unwrap_err();
+    assert_eq!(err.error.kind(), std::io::ErrorKind::AlreadyExists);
+    fs::remove_file(&target).unwrap();
+    assert_eq!(
+        err.path.persist_noclobber_with_strategy(&target).unwrap(),
+        strategy
+    );
+}
+
 #[test]
 fn test_customnamed() {
     let tmpfile = Builder::new()
@@ -95,6 +125,121 @@ fn test_customnamed() {
     assert_eq!(name.len(), 18);
 }
 
+#[test]
+fn test_name_with() {
+    let tmpfile = Builder::new()
+        .prefix("tmp")
+        .rand_bytes(8)
+        .name_with(Hex)
+        .tempfile()
+        .unwrap();
+    let name = tmpfile.path().file_name().unwrap().to_str().unwrap();
+    assert_eq!(name.len(), 11);
+    assert!(name[3..].chars().all(|c| "0123456789abcdef".contains(c)));
+
+    let tmpfile = Builder::new().name_with(ProcessId).tempfile().unwrap();
+    let name = tmpfile.path().file_name().unwrap().to_str().unwrap();
+    let pid = name[4..].split(|c| c == '.' || c == '-').next().unwrap();
+    assert!(pid.parse::<u32>().unwrap() > 0);
+
+    let first = Builder::new().name_with(Timestamp).tempfile().unwrap();
+    let second = Builder::new().name_with(Timestamp).tempfile().unwrap();
+    assert!(first.path() < second.path());
+
+    // Colliding names are retried.
+    let dir = tempfile::tempdir().unwrap();
+    let counter = std::sync::atomic::AtomicUsize::new(0);
+    let mut builder = Builder::new();
+    builder.name_with(move |_| {
+        let n = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        (n / 2).to_string()
+    });
+    let a = builder.tempfile_in(dir.path()).unwrap();
+    let b = builder.tempfile_in(dir.path()).unwrap();
+    assert_eq!(a.path().file_name().unwrap(), ".tmp0");
+    assert_eq!(b.path().file_name().unwrap(), ".tmp1");
+}
+
+#[test]
+fn test_retries() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut builder = Builder::new();
+    builder.prefix("taken").rand_bytes(0).retries(5);
+    let _taken = builder.tempfile_in(dir.path()).unwrap();
+
+    let err = builder.tempfile_in(dir.path()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    let collision = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<CollisionError>())
+        .unwrap();
+    assert_eq!(collision.attempts(), 5);
+    assert_eq!(collision.dir(), dir.path());
+    assert_eq!(collision.pattern(), "taken");
+
+    builder.retries(0);
+    let err = builder.tempfile_in(dir.path()).unwrap_err();
+    let collision = err
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<CollisionError>())
+        .unwrap();
+    assert_eq!(collision.attempts(), 1);
+
+    // Four collisions add a character to the name.
+    let tmpfile = builder
+        .retries(10)
+        .adaptive_rand_bytes(true)
+        .name_with(|len: usize| "a".repeat(len))
+        .tempfile_in(dir.path())
+        .unwrap();
+    assert_eq!(tmpfile.path().file_name().unwrap(), "takena");
+}
+
+#[test]
+fn test_invalid_name() {
+    let dir = tempfile::tempdir().unwrap();
+    for &(prefix, rand_bytes) in &[("sub/x", 6), ("", 0), (".", 0), ("..", 0)] {
+        let err = Builder::new()
+            .prefix(prefix)
+            .rand_bytes(rand_bytes)
+            .tempfile_in(dir.path())
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_seed() {
+    let dir = tempfile::tempdir().unwrap();
+    let first: Vec<_> = {
+        let mut builder = Builder::new();
+        builder.seed(7);
+        (0..3)
+            .map(|_| builder.tempfile_in(dir.path()).unwrap().path().to_owned())
+            .collect()
+    };
+    let second: Vec<_> = {
+        let mut builder = Builder::new();
+        builder.seed(7);
+        (0..3)
+            .map(|_| builder.tempfile_in(dir.path()).unwrap().path().to_owned())
+            .collect()
+    };
+    assert_eq!(first, second);
+    assert!(first[0] != first[1]);
+
+    let tmpfile = Builder::new()
+        .rng(|dest: &mut [u8]| {
+            for byte in dest.iter_mut() {
+                *byte = 10;
+            }
+        })
+        .tempfile_in(dir.path())
+        .unwrap();
+    assert_eq!(tmpfile.path().file_name().unwrap(), ".tmpaaaaaa");
+}
+
 #[test]
 fn test_reopen() {
     let source = NamedTempFile::new().unwrap();
@@ -108,6 +253,50 @@ fn test_reopen() {
     assert_eq!("abcde", buf);
 }
 
+#[test]
+fn test_reopen_read_only_and_append() {
+    let mut source = NamedTempFile::new().unwrap();
+    write!(source, "abcde").unwrap();
+
+    let mut reader = source.reopen_read_only().unwrap();
+    let mut logger = source.reopen_append().unwrap();
+    assert!(write!(reader, "x").is_err());
+    write!(logger, "fghij").unwrap();
+    write!(source, "z").unwrap();
+
+    let mut buf = String::new();
+    reader.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcdezghij", buf);
+
+    // Read-only handles can still be opened once the file is read-only.
+    let mut permissions = fs::metadata(source.path()).unwrap().permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(source.path(), permissions).unwrap();
+    let mut reader = source.reopen_read_only().unwrap();
+    buf.clear();
+    reader.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcdezghij", buf);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_reopen_replaced() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = NamedTempFile::new_in(dir.path()).unwrap();
+    let other = dir.path().join("other");
+    fs::write(&other, "abcde").unwrap();
+    fs::rename(&other, source.path()).unwrap();
+
+    for result in vec![
+        source.reopen(),
+        source.reopen_read_only(),
+        source.reopen_append(),
+    ] {
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    }
+    assert_eq!(fs::read_to_string(source.path()).unwrap(), "abcde");
+}
+
 #[test]
 fn test_into_file() {
     let mut file = NamedTempFile::new().unwrap();
@@ -201,3 +390,117 @@ fn test_temppath_persist_noclobber() {
     assert_eq!("abcde", buf);
     std::fs::remove_file(&persist_path).unwrap();
 }
+
+#[cfg(unix)]
+#[test]
+fn test_permissions() {
+    use std::fs::Permissions;
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmpfile = NamedTempFile::new().unwrap();
+    let mode = tmpfile.as_file().metadata().unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let tmpfile = Builder::new()
+        .permissions(Permissions::from_mode(0o640))
+        .tempfile()
+        .unwrap();
+    let mode = tmpfile.as_file().metadata().unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+}
+
+#[test]
+fn test_persist_noclobber_existing() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let old_path = tmpfile.path().to_path_buf();
+    let persist_target = NamedTempFile::new().unwrap();
+
+    let err = tmpfile.persist_noclobber(persist_target.path()).unwrap_err();
+    assert_eq!(err.error.kind(), std::io::ErrorKind::AlreadyExists);
+    assert!(exists(&old_path));
+    assert!(exists(persist_target.path()));
+}
+
+#[test]
+fn test_persist_durable() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let persist_path = tmpdir.path().join("persisted");
+
+    let mut tmpfile = NamedTempFile::new_in(&tmpdir).unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    tmpfile.persist_durable(&persist_path).unwrap();
+
+    let mut tmpfile = NamedTempFile::new_in(&tmpdir).unwrap();
+    write!(tmpfile, "fghij").unwrap();
+    let tmppath = tmpfile.into_temp_path();
+    let tmppath = tmppath
+        .persist_noclobber_durable(&persist_path)
+        .unwrap_err()
+        .path;
+    tmppath.persist_durable(&persist_path).unwrap();
+    assert_eq!(std::fs::read_dir(&tmpdir).unwrap().count(), 1);
+
+    let mut f = File::open(&persist_path).unwrap();
+    let mut buf = String::new();
+    f.read_to_string(&mut buf).unwrap();
+    assert_eq!("fghij", buf);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_persist_exchange() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("config");
+    File::create(&target).unwrap().write_all(b"old").unwrap();
+
+    let mut tmpfile = NamedTempFile::new_in(dir.path()).unwrap();
+    let tmp_path = tmpfile.path().to_path_buf();
+    tmpfile.write_all(b"new").unwrap();
+
+    let (mut file, previous) = tmpfile.persist_exchange(&target).unwrap();
+    assert_eq!(previous.to_path_buf(), tmp_path);
+    let mut buf = String::new();
+    File::open(&target).unwrap().read_to_string(&mut buf).unwrap();
+    assert_eq!(buf, "new");
+    buf.clear();
+    File::open(&previous)
+        .unwrap()
+        .read_to_string(&mut buf)
+        .unwrap();
+    assert_eq!(buf, "old");
+    file.write_all(b"er").unwrap();
+    drop(previous);
+    assert!(!tmp_path.exists());
+
+    // The target must be an existing file.
+    let tmpfile = NamedTempFile::new_in(dir.path()).unwrap();
+    let err = tmpfile
+        .persist_exchange(dir.path().join("missing"))
+        .unwrap_err();
+    let err = err.file.persist_exchange(dir.path()).unwrap_err();
+    assert!(err.file.path().exists());
+}
+
+#[test]
+fn test_keep() {
+    let mut tmpfile = NamedTempFile::new().unwrap();
+    let path = tmpfile.path().to_path_buf();
+    tmpfile.keep(true);
+    drop(tmpfile);
+    assert!(exists(&path));
+
+    let mut tmppath = NamedTempFile::new().unwrap().into_temp_path();
+    tmppath.keep(true);
+    tmppath.keep(false);
+    let tmppath_path = tmppath.to_path_buf();
+    drop(tmppath);
+    assert!(!exists(&tmppath_path));
+
+    let tmpfile = Builder::new().keep(true).tempfile().unwrap();
+    let builder_path = tmpfile.path().to_path_buf();
+    tmpfile.close().unwrap();
+    assert!(exists(&builder_path));
+
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&builder_path).unwrap();
+}
@@ -0,0 +1,122 @@
+extern crate tempfile;
+
+use std::fs::{self, File};
+use std::time::Duration;
+use tempfile::{Builder, ProcessId, ReapOptions};
+
+fn names(dir: &::std::path::Path) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_reap() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut builder = Builder::new();
+    builder.prefix("svc-").suffix(".dat").keep(true);
+
+    builder.tempfile_in(dir.path()).unwrap();
+    let subdir = builder.tempdir_in(dir.path()).unwrap();
+    File::create(subdir.path().join("data")).unwrap();
+    File::create(dir.path().join("svc-other.dat")).unwrap();
+    File::create(dir.path().join("svc-abc.txt")).unwrap();
+    File::create(dir.path().join("unrelated")).unwrap();
+
+    let removed = tempfile::reap(
+        dir.path(),
+        &ReapOptions::new(&builder, Duration::from_secs(3600)),
+    )
+    .unwrap();
+    assert!(removed.is_empty());
+    assert_eq!(names(dir.path()).len(), 5);
+
+    let removed = tempfile::reap(
+        dir.path(),
+        &ReapOptions::new(&builder, Duration::from_secs(0)),
+    )
+    .unwrap();
+    assert_eq!(removed.len(), 2);
+    assert!(removed.contains(&subdir.path().to_owned()));
+    assert_eq!(
+        names(dir.path()),
+        vec!["svc-abc.txt", "svc-other.dat", "unrelated"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_reap_orphaned() {
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut builder = Builder::new();
+    builder.prefix("svc-").name_with(ProcessId);
+
+    let alive = builder.tempfile_in(dir.path()).unwrap();
+    File::create(dir.path().join("svc-abcdef")).unwrap();
+
+    // Pretend a process that has exited created a file on this host.
+    let name = alive.path().file_name().unwrap().to_str().unwrap();
+    let host_and_random = &name[name.find('.').unwrap() + 1..];
+    let mut child = Command::new("true").spawn().unwrap();
+    let dead_pid = child.id();
+    child.wait().unwrap();
+    let orphan = dir
+        .path()
+        .join(format!("svc-{}.{}", dead_pid, host_and_random));
+    File::create(&orphan).unwrap();
+
+    let removed = tempfile::reap(
+        dir.path(),
+        ReapOptions::new(&builder, Duration::from_secs(0)).only_orphaned(true),
+    )
+    .unwrap();
+    assert_eq!(removed, vec![orphan]);
+    assert!(alive.path().exists());
+    assert!(dir.path().join("svc-abcdef").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_is_orphaned() {
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut builder = Builder::new();
+    builder.prefix("worker-").name_with(ProcessId);
+
+    let alive = builder.tempdir_in(dir.path()).unwrap();
+    assert!(!builder.is_orphaned(alive.path()));
+    assert!(!builder.is_orphaned(dir.path().join("worker-abcdef")));
+    assert!(!Builder::new().is_orphaned(alive.path()));
+
+    let name = alive.path().file_name().unwrap().to_str().unwrap();
+    let owner = &name["worker-".len()..name.find('.').unwrap()];
+    let host_and_random = &name[name.find('.').unwrap() + 1..];
+
+    let mut child = Command::new("true").spawn().unwrap();
+    let dead_pid = child.id();
+    child.wait().unwrap();
+    let orphan = dir
+        .path()
+        .join(format!("worker-{}.{}", dead_pid, host_and_random));
+    assert!(builder.is_orphaned(&orphan));
+
+    // The creator ran on a different host, so it can't be checked.
+    let foreign = dir
+        .path()
+        .join(format!("worker-{}.elsewhere.abcdef", dead_pid));
+    assert!(!builder.is_orphaned(&foreign));
+
+    // A live process that reused the ID of the creator.
+    if let Some(dash) = owner.find('-') {
+        let reused = dir
+            .path()
+            .join(format!("worker-{}-0.{}", &owner[..dash], host_and_random));
+        assert!(builder.is_orphaned(&reused));
+    }
+}
@@ -0,0 +1,65 @@
+extern crate tempfile;
+
+use std::fs;
+use std::io::ErrorKind;
+use std::thread;
+use tempfile::{Builder, SharedTempFile};
+
+#[test]
+fn test_concurrent_writes() {
+    let file = SharedTempFile::new().unwrap();
+    let threads: Vec<_> = (0..8u8)
+        .map(|i| {
+            let file = file.clone();
+            thread::spawn(move || {
+                let chunk = vec![b'a' + i; 1024];
+                file.write_all_at(&chunk, u64::from(i) * 1024).unwrap();
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    let contents = fs::read(file.path()).unwrap();
+    assert_eq!(contents.len(), 8 * 1024);
+    for (i, chunk) in contents.chunks(1024).enumerate() {
+        assert!(chunk.iter().all(|&b| b == b'a' + i as u8));
+    }
+}
+
+#[test]
+fn test_read_at() {
+    let file = SharedTempFile::new().unwrap();
+    file.write_all_at(b"abcde", 0).unwrap();
+
+    let mut buf = [0; 3];
+    file.read_exact_at(&mut buf, 1).unwrap();
+    assert_eq!(&buf, b"bcd");
+    assert_eq!(file.read_at(&mut buf, 3).unwrap(), 2);
+    assert_eq!(&buf[..2], b"de");
+
+    let err = file.read_exact_at(&mut buf, 4).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+    // Writing past the end fills the gap with zeros.
+    file.write_all_at(b"z", 7).unwrap();
+    assert_eq!(fs::read(file.path()).unwrap(), b"abcde\0\0z");
+}
+
+#[test]
+fn test_drop_last_clone() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = SharedTempFile::from(Builder::new().prefix("shared").tempfile_in(&dir).unwrap());
+    let path = file.path().to_owned();
+    let other = file.clone();
+
+    let file = file.try_into_inner().unwrap_err();
+    drop(file);
+    assert!(path.exists());
+
+    let file = other.try_into_inner().unwrap();
+    assert_eq!(file.path(), path);
+    drop(file);
+    assert!(!path.exists());
+}
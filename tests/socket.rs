@@ -0,0 +1,65 @@
+#![cfg(unix)]
+
+extern crate tempfile;
+
+use std::io::{ErrorKind, Read, Write};
+use tempfile::{Builder, TempDir};
+
+#[test]
+fn test_unix_listener() {
+    let dir = TempDir::new().unwrap();
+    let server = Builder::new()
+        .prefix("server")
+        .unix_listener_in(dir.path())
+        .unwrap();
+    let path = server.path().to_owned();
+    assert_eq!(path.parent(), Some(dir.path()));
+    assert!(path
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .starts_with("server"));
+    assert!(path.exists());
+
+    let mut client = server.connect().unwrap();
+    client.write_all(b"abcde").unwrap();
+    let (mut connection, _) = server.as_listener().accept().unwrap();
+    let mut buf = [0; 5];
+    connection.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"abcde");
+
+    drop(server);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_keep_and_close() {
+    let dir = TempDir::new().unwrap();
+    let mut server = Builder::new().unix_listener_in(dir.path()).unwrap();
+    let path = server.path().to_owned();
+    server.keep(true);
+    drop(server);
+    assert!(path.exists());
+
+    let server = Builder::new().unix_listener_in(dir.path()).unwrap();
+    let path = server.path().to_owned();
+    server.close().unwrap();
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_path_too_long() {
+    let dir = TempDir::new().unwrap();
+    let long = dir.path().join("x".repeat(120));
+    let err = Builder::new().unix_listener_in(&long).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("sun_path"));
+}
+
+#[test]
+fn test_unix_listener_default_dir() {
+    let server = Builder::new().unix_listener().unwrap();
+    assert!(server.path().exists());
+    assert!(server.connect().is_ok());
+}
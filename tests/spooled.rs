@@ -0,0 +1,83 @@
+extern crate tempfile;
+use std::io::{Read, Seek, SeekFrom, Write};
+use tempfile::{spooled_tempfile, SpooledData, SpooledTempFile};
+
+#[test]
+fn test_automatic_rollover() {
+    let mut t = spooled_tempfile(10);
+    let mut buf = Vec::new();
+
+    assert!(!t.is_rolled());
+    assert_eq!(t.read_to_end(&mut buf).unwrap(), 0);
+    assert_eq!(buf.as_slice(), b"");
+    buf.clear();
+
+    assert_eq!(t.write(b"abcde").unwrap(), 5);
+
+    assert!(!t.is_rolled());
+    assert_eq!(t.seek(SeekFrom::Start(0)).unwrap(), 0);
+    assert_eq!(t.read_to_end(&mut buf).unwrap(), 5);
+    assert_eq!(buf.as_slice(), b"abcde");
+
+    assert_eq!(t.write(b"fghijklmno").unwrap(), 10);
+
+    assert!(t.is_rolled());
+    buf.clear();
+    assert_eq!(t.seek(SeekFrom::Start(0)).unwrap(), 0);
+    assert_eq!(t.read_to_end(&mut buf).unwrap(), 15);
+    assert_eq!(buf.as_slice(), b"abcdefghijklmno");
+}
+
+#[test]
+fn test_explicit_rollover() {
+    let mut t = SpooledTempFile::new(100);
+    write!(t, "abcde").unwrap();
+    t.seek(SeekFrom::Start(2)).unwrap();
+
+    t.roll_over().unwrap();
+    assert!(t.is_rolled());
+
+    // The position is preserved across the roll over.
+    let mut buf = String::new();
+    t.read_to_string(&mut buf).unwrap();
+    assert_eq!("cde", buf);
+}
+
+#[test]
+fn test_set_len() {
+    let mut t = spooled_tempfile(10);
+    write!(t, "abcde").unwrap();
+
+    t.set_len(2).unwrap();
+    assert!(!t.is_rolled());
+    t.set_len(20).unwrap();
+    assert!(t.is_rolled());
+
+    t.seek(SeekFrom::Start(0)).unwrap();
+    let mut buf = Vec::new();
+    t.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf.len(), 20);
+    assert_eq!(&buf[..3], b"ab\0");
+}
+
+#[test]
+fn test_into_inner() {
+    let mut t = spooled_tempfile(10);
+    write!(t, "abcde").unwrap();
+    match t.into_inner() {
+        SpooledData::InMemory(cursor) => assert_eq!(cursor.into_inner(), b"abcde"),
+        SpooledData::OnDisk(_) => panic!("should not have rolled over"),
+    }
+
+    let mut t = spooled_tempfile(2);
+    write!(t, "abcde").unwrap();
+    match t.into_inner() {
+        SpooledData::InMemory(_) => panic!("should have rolled over"),
+        SpooledData::OnDisk(mut file) => {
+            file.seek(SeekFrom::Start(0)).unwrap();
+            let mut buf = String::new();
+            file.read_to_string(&mut buf).unwrap();
+            assert_eq!("abcde", buf);
+        }
+    }
+}
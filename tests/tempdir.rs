@@ -65,6 +65,195 @@ fn test_customnamed() {
     assert_eq!(name.len(), 24);
 }
 
+#[cfg(unix)]
+#[test]
+fn test_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmpdir = Builder::new()
+        .permissions(fs::Permissions::from_mode(0o700))
+        .tempdir()
+        .unwrap();
+    let mode = fs::metadata(tmpdir.path()).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+
+    // Without read permission, the directory can't be opened, but it can still be created.
+    let tmpdir = Builder::new()
+        .permissions(fs::Permissions::from_mode(0o300))
+        .tempdir()
+        .unwrap();
+    let mode = fs::metadata(tmpdir.path()).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o300);
+    fs::set_permissions(tmpdir.path(), fs::Permissions::from_mode(0o700)).unwrap();
+}
+
+#[test]
+fn test_keep() {
+    let mut tmpdir = TempDir::new().unwrap();
+    let path = tmpdir.path().to_path_buf();
+    tmpdir.keep(true);
+    drop(tmpdir);
+    assert!(path.exists());
+    fs::remove_dir(&path).unwrap();
+
+    let tmpdir = Builder::new().keep(true).tempdir().unwrap();
+    let path = tmpdir.path().to_path_buf();
+    tmpdir.close().unwrap();
+    assert!(path.exists());
+    fs::remove_dir(&path).unwrap();
+}
+
+#[test]
+fn test_persist() {
+    use std::io::{ErrorKind, Read, Write};
+
+    let root = TempDir::new().unwrap();
+    let target = root.path().join("published");
+
+    let staging = TempDir::new_in(root.path()).unwrap();
+    let staged = staging.path().to_path_buf();
+    fs::create_dir(staging.path().join("sub")).unwrap();
+    fs::File::create(staging.path().join("sub/data"))
+        .unwrap()
+        .write_all(b"abcde")
+        .unwrap();
+    staging.persist(&target).unwrap();
+    assert!(!staged.exists());
+    let mut buf = String::new();
+    fs::File::open(target.join("sub/data"))
+        .unwrap()
+        .read_to_string(&mut buf)
+        .unwrap();
+    assert_eq!(buf, "abcde");
+
+    // Something already exists at the target.
+    let staging = TempDir::new_in(root.path()).unwrap();
+    let err = staging.persist_noclobber(&target).unwrap_err();
+    assert_eq!(err.error.kind(), ErrorKind::AlreadyExists);
+    let staged = err.dir.path().to_path_buf();
+    assert!(staged.exists());
+    assert!(target.join("sub/data").exists());
+
+    // A non-empty directory is never replaced.
+    let staging: TempDir = err.into();
+    assert!(staging.persist(&target).is_err());
+    assert!(!staged.exists());
+    assert!(target.join("sub/data").exists());
+
+    let staging = TempDir::new_in(root.path()).unwrap();
+    staging.persist_noclobber(root.path().join("other")).unwrap();
+    assert!(root.path().join("other").is_dir());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_persist_exchange() {
+    let root = TempDir::new().unwrap();
+    let target = root.path().join("assets");
+    fs::create_dir(&target).unwrap();
+    fs::File::create(target.join("old")).unwrap();
+
+    let staging = TempDir::new_in(root.path()).unwrap();
+    let staged = staging.path().to_path_buf();
+    fs::File::create(staging.path().join("new")).unwrap();
+
+    let previous = staging.persist_exchange(&target).unwrap();
+    assert_eq!(previous.path(), staged);
+    assert!(target.join("new").exists());
+    assert!(!target.join("old").exists());
+    assert!(staged.join("old").exists());
+    drop(previous);
+    assert!(!staged.exists());
+    assert!(target.join("new").exists());
+
+    // The target must be an existing directory.
+    let staging = TempDir::new_in(root.path()).unwrap();
+    let err = staging
+        .persist_exchange(root.path().join("missing"))
+        .unwrap_err();
+    fs::File::create(root.path().join("file")).unwrap();
+    let err = err
+        .dir
+        .persist_exchange(root.path().join("file"))
+        .unwrap_err();
+    assert!(err.dir.path().is_dir());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dir_handle() {
+    use std::io::{Read, Write};
+    use std::os::unix::fs::symlink;
+
+    let tmpdir = Builder::new().dir_handle(true).tempdir().unwrap();
+    let path = tmpdir.path().to_path_buf();
+
+    tmpdir.create_dir("sub").unwrap();
+    assert!(path.join("sub").is_dir());
+    {
+        let mut f = tmpdir.create_file("sub/file").unwrap();
+        f.write_all(b"abcde").unwrap();
+    }
+    let mut buf = String::new();
+    tmpdir
+        .open("sub/file")
+        .unwrap()
+        .read_to_string(&mut buf)
+        .unwrap();
+    assert_eq!("abcde", buf);
+
+    // Escaping the directory and following a symbolic link are refused.
+    assert!(tmpdir.open("../file").is_err());
+    assert!(tmpdir.create_file(&path.join("absolute")).is_err());
+    symlink(path.join("sub/file"), path.join("link")).unwrap();
+    assert!(tmpdir.open("link").is_err());
+
+    let named = Builder::new().suffix(".txt").tempfile_at(&tmpdir).unwrap();
+    assert_eq!(named.path().parent(), Some(path.as_path()));
+    assert!(named.path().to_str().unwrap().ends_with(".txt"));
+    named.close().unwrap();
+
+    // Names must be a single path component, also relative to the handle.
+    let err = Builder::new().prefix("sub/x").tempfile_at(&tmpdir).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = Builder::new()
+        .prefix("..")
+        .rand_bytes(0)
+        .tempfile_at(&tmpdir)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    tmpdir.tempfile().unwrap();
+
+    tmpdir.remove("link").unwrap();
+    assert!(tmpdir.remove("sub").is_err());
+    tmpdir.remove("sub/file").unwrap();
+    tmpdir.remove("sub").unwrap();
+    assert_eq!(fs::read_dir(&path).unwrap().count(), 0);
+
+    let tmpdir = TempDir::new().unwrap();
+    assert!(tmpdir.create_file("file").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_rm_tempdir_symlink() {
+    use std::os::unix::fs::symlink;
+
+    let outside = TempDir::new().unwrap();
+    fs::File::create(outside.path().join("precious")).unwrap();
+
+    let tmpdir = TempDir::new().unwrap();
+    let path = tmpdir.path().to_path_buf();
+    fs::create_dir_all(path.join("a/b")).unwrap();
+    fs::File::create(path.join("a/b/file")).unwrap();
+    symlink(outside.path(), path.join("a/link")).unwrap();
+    tmpdir.close().unwrap();
+
+    assert!(!path.exists());
+    assert!(outside.path().join("precious").exists());
+}
+
 fn test_rm_tempdir() {
     let (tx, rx) = channel();
     let f = move || -> () {
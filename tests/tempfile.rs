@@ -60,3 +60,63 @@ fn test_pathological_cleaner() {
     drop(tx);
     cleaner_thread.join().expect("The cleaner thread failed");
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_persist_anonymous() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let mut tmpfile = tempfile::Builder::new()
+        .linkable_tempfile_in(&tmpdir)
+        .unwrap();
+    write!(tmpfile, "abcde").unwrap();
+    assert_eq!(fs::read_dir(&tmpdir).unwrap().count(), 0);
+
+    let persist_path = tmpdir.path().join("persisted");
+    tempfile::persist_anonymous_noclobber(&tmpfile, &persist_path).unwrap();
+    let err = tempfile::persist_anonymous_noclobber(&tmpfile, &persist_path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+
+    // Replace the existing file.
+    let mut other = tempfile::Builder::new()
+        .linkable_tempfile_in(&tmpdir)
+        .unwrap();
+    write!(other, "fghij").unwrap();
+    tempfile::persist_anonymous(&other, &persist_path).unwrap();
+    assert_eq!(fs::read_dir(&tmpdir).unwrap().count(), 1);
+
+    let mut buf = String::new();
+    fs::File::open(&persist_path)
+        .unwrap()
+        .read_to_string(&mut buf)
+        .unwrap();
+    assert_eq!("fghij", buf);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_reopen_anonymous() {
+    let mut writer = tempfile::tempfile().unwrap();
+    let mut first = tempfile::reopen_anonymous(&writer).unwrap();
+    let mut second = tempfile::reopen_anonymous(&writer).unwrap();
+    write!(writer, "abcde").unwrap();
+
+    // Each handle has its own cursor.
+    let mut buf = [0; 2];
+    first.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ab");
+    let mut buf = String::new();
+    second.read_to_string(&mut buf).unwrap();
+    assert_eq!("abcde", buf);
+    buf.clear();
+    first.read_to_string(&mut buf).unwrap();
+    assert_eq!("cde", buf);
+
+    let path = tempfile::anonymous_path(&writer);
+    assert!(path.starts_with("/proc"));
+    let mut buf = String::new();
+    fs::File::open(&path)
+        .unwrap()
+        .read_to_string(&mut buf)
+        .unwrap();
+    assert_eq!("abcde", buf);
+}
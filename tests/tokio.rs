@@ -0,0 +1,121 @@
+#![cfg(feature = "tokio")]
+
+extern crate tempfile;
+extern crate tokio;
+
+use std::fs;
+use std::io::{ErrorKind, SeekFrom};
+use tempfile::tokio::{AsyncNamedTempFile, AsyncTempDir};
+use tempfile::{NamedTempFile, TempDir};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::runtime::{Builder, Runtime};
+
+fn runtime() -> Runtime {
+    Builder::new_current_thread().build().unwrap()
+}
+
+#[test]
+fn test_tempfile() {
+    let runtime = runtime();
+    let mut file = runtime.block_on(tempfile::tokio::tempfile()).unwrap();
+    runtime.block_on(file.write_all(b"abcde")).unwrap();
+    runtime.block_on(file.seek(SeekFrom::Start(0))).unwrap();
+    let mut buf = String::new();
+    runtime.block_on(file.read_to_string(&mut buf)).unwrap();
+    assert_eq!("abcde", buf);
+}
+
+#[test]
+fn test_persist() {
+    let runtime = runtime();
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("persisted");
+
+    let mut file = runtime
+        .block_on(AsyncNamedTempFile::new_in(dir.path()))
+        .unwrap();
+    let path = file.path().to_owned();
+    runtime.block_on(file.write_all(b"abcde")).unwrap();
+    runtime.block_on(file.seek(SeekFrom::Start(1))).unwrap();
+    let mut buf = String::new();
+    runtime.block_on(file.read_to_string(&mut buf)).unwrap();
+    assert_eq!("bcde", buf);
+
+    runtime.block_on(file.persist(&target)).unwrap();
+    assert!(!path.exists());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "abcde");
+
+    let mut file = runtime
+        .block_on(AsyncNamedTempFile::new_in(dir.path()))
+        .unwrap();
+    runtime.block_on(file.write_all(b"fghij")).unwrap();
+    let err = runtime
+        .block_on(file.persist_noclobber(&target))
+        .unwrap_err();
+    assert_eq!(err.error.kind(), ErrorKind::AlreadyExists);
+    assert!(err.file.path().exists());
+    assert_eq!(fs::read_to_string(err.file.path()).unwrap(), "fghij");
+    assert_eq!(fs::read_to_string(&target).unwrap(), "abcde");
+}
+
+#[test]
+fn test_close_and_drop() {
+    let runtime = runtime();
+    let dir = tempfile::tempdir().unwrap();
+
+    let file = runtime
+        .block_on(AsyncNamedTempFile::new_in(dir.path()))
+        .unwrap();
+    let path = file.path().to_owned();
+    runtime.block_on(file.close()).unwrap();
+    assert!(!path.exists());
+
+    // Dropped inside the runtime, the file is deleted on the blocking thread pool, which is
+    // waited for when the runtime shuts down.
+    let file = AsyncNamedTempFile::from(NamedTempFile::new_in(dir.path()).unwrap());
+    let path = file.path().to_owned();
+    {
+        let _guard = runtime.enter();
+        drop(file);
+    }
+    drop(runtime);
+    assert!(!path.exists());
+
+    // Without a runtime, it's deleted right away.
+    let file = AsyncNamedTempFile::from(NamedTempFile::new_in(dir.path()).unwrap());
+    let path = file.path().to_owned();
+    drop(file);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_tempdir() {
+    let runtime = runtime();
+    let parent = tempfile::tempdir().unwrap();
+
+    let dir = runtime
+        .block_on(AsyncTempDir::new_in(parent.path()))
+        .unwrap();
+    let path = dir.path().to_owned();
+    fs::File::create(path.join("file")).unwrap();
+    runtime.block_on(dir.close()).unwrap();
+    assert!(!path.exists());
+
+    let dir = AsyncTempDir::from(TempDir::new_in(parent.path()).unwrap());
+    let path = dir.path().to_owned();
+    fs::create_dir(path.join("sub")).unwrap();
+    {
+        let _guard = runtime.enter();
+        drop(dir);
+    }
+    drop(runtime);
+    assert!(!path.exists());
+
+    let mut dir = AsyncTempDir::from(TempDir::new_in(parent.path()).unwrap());
+    dir.keep(true);
+    let dir = dir.into_temp_dir();
+    let path = dir.path().to_owned();
+    drop(dir);
+    assert!(path.exists());
+    fs::remove_dir(&path).unwrap();
+}
@@ -0,0 +1,55 @@
+#![cfg(unix)]
+
+extern crate libc;
+extern crate tempfile;
+
+use std::fs::{self, Permissions};
+use std::os::unix::fs::PermissionsExt;
+use tempfile::Builder;
+
+// The umask is shared by the whole process, so this test has a file to itself.
+#[test]
+fn test_permissions_ignore_umask() {
+    let old = unsafe { libc::umask(0o077) };
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut builder = Builder::new();
+    builder.permissions(Permissions::from_mode(0o644));
+    let file = builder.tempfile_in(dir.path());
+    let at = Builder::new()
+        .dir_handle(true)
+        .tempdir_in(dir.path())
+        .and_then(|handle| {
+            let file = builder.tempfile_at(&handle)?;
+            let mode = file.as_file().metadata()?.permissions().mode();
+            Ok((handle, mode))
+        });
+    let subdir = Builder::new()
+        .permissions(Permissions::from_mode(0o755))
+        .tempdir_in(dir.path());
+    let linkable = Builder::new()
+        .permissions(Permissions::from_mode(0o660))
+        .linkable_tempfile_in(dir.path());
+    let fifo = Builder::new()
+        .permissions(Permissions::from_mode(0o620))
+        .fifo_in(dir.path());
+
+    unsafe { libc::umask(old) };
+
+    let file = file.unwrap();
+    let mode = file.as_file().metadata().unwrap().permissions().mode();
+    assert_eq!(mode & 0o7777, 0o644);
+    assert_eq!(at.unwrap().1 & 0o7777, 0o644);
+    let mode = fs::metadata(subdir.unwrap().path())
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o7777, 0o755);
+    let mode = linkable.unwrap().metadata().unwrap().permissions().mode();
+    assert_eq!(mode & 0o7777, 0o660);
+    let mode = fs::metadata(fifo.unwrap().path())
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o7777, 0o620);
+}